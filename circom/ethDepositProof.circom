@@ -55,4 +55,7 @@ template EthDepositProof(depthI) {
     );
 }
 
-component main = EthDepositProof(32);
\ No newline at end of file
+// recipient, mint and amount are public so a proof authorises exactly one transfer,
+// sourceChainId and tokenMint so it only pays out on the link the deposit was made on,
+// old_root is public so the program can check it against its stored nullifier root
+component main {public [sourceChainId, destChainAddr, destChainMintAddr, tokenMint, amount, old_root]} = EthDepositProof(32);
\ No newline at end of file
@@ -11,7 +11,8 @@ interface DepositRecord {
     owner: string,
     source_chain_id: number,
     dest_chain_id: number,
-    dest_chain_addr: string,
+    // raw recipient, left-padded to 32 bytes
+    dest_chain_addr: number[],
     dest_chain_mint_addr: string,
     mint: string,
     amount: string,
@@ -39,17 +40,6 @@ function pubkeyToField(pubkey: string): string {
     }
 }
 
-function stringToField(str: string): string {
-    if(str.startsWith('0x')) {
-        return hexToField(str);
-    }
-
-    const encoder = new TextEncoder();
-    const bytes = encoder.encode(str);
-    const bn = new BN(bytes);
-    return bn.toString(10);
-}
-
 function computePathIndices(leafIndex: number, levels: number): number[] {
     const pathIndices=[];
     let index = leafIndex;
@@ -77,7 +67,7 @@ function generateCircuitInput(): any {
         const stateRoot = hexToField(proofData.root);
         const amount = recordData.amount;
         const destChainId = recordData.dest_chain_id.toString();
-        const destChainAddr = stringToField(recordData.dest_chain_addr);
+        const destChainAddr = new BN(recordData.dest_chain_addr).toString(10);
 
         const accountHash = hexToField(proofData.hash);
         const leafIndex = proofData.leafIndex.toString();
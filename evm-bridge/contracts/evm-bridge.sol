@@ -4,6 +4,7 @@ pragma solidity ^0.8.20;
 import "./SolDepositVerifier.sol";
 
 import "@openzeppelin/contracts/token/ERC20/IERC20.sol";
+import "@openzeppelin/contracts/token/ERC20/extensions/IERC20Metadata.sol";
 import "@openzeppelin/contracts/token/ERC20/utils/SafeERC20.sol";
 import "@openzeppelin/contracts/access/Ownable.sol";
 
@@ -27,7 +28,7 @@ contract SolanaEVMBridge is Ownable {
         address destChainAddr;        // Destination address (base58 format)
         address destChainMintAddr;   // ERC20 token contract address
         string mint;                 // Solana mint pubkey
-        uint256 amount;              // Token amount
+        uint256 amount;              // Token amount, in the link's canonical decimals
         string timestamp;           // Deposit timestamp
         uint256 depositId;           // Unique deposit ID
     }
@@ -61,6 +62,8 @@ contract SolanaEVMBridge is Ownable {
         uint256 amount
     );
     
+    event DepositCancelled(string owner, uint256 indexed depositId);
+
     event StateRootUpdated(
         uint256 indexed stateRoot,
         uint256 blockHeight,
@@ -73,6 +76,12 @@ contract SolanaEVMBridge is Ownable {
     mapping(address => bool) public authorizedRelayers;      // Can update state roots
     mapping(string => address) public addressMapping;        // Base58 to Ethereum address mapping
     mapping(string => address) public tokenMapping;          // Solana mint to ERC20 mapping
+    // 10 ** (token decimals - canonical decimals of its links), 0 while the token is unregistered
+    mapping(address => uint256) public canonicalScale;
+    // keyed by depositKey; a deposit is released at most once, and never after
+    // it was cancelled so its owner could refund it on solana
+    mapping(bytes32 => bool) public releasedDeposits;
+    mapping(bytes32 => bool) public cancelledDeposits;
     
     uint256 public constant SOLANA_CHAIN_ID = 1;
     uint256 public minAmount = 1; // min amount to withdraw
@@ -102,13 +111,19 @@ contract SolanaEVMBridge is Ownable {
         require(bytes(destChainMintAddr).length > 0, "Invalid destination mint address");
         require(tokenMint != address(0), "Invalid token mint address");
 
+        uint256 scale = canonicalScale[tokenMint];
+        require(scale != 0, "Token not registered");
+        // only whole canonical units can be bridged, the dust stays with the depositor
+        uint256 bridged = amount - (amount % scale);
+        require(bridged > 0, "Amount too small");
+
         IERC20 token = IERC20(tokenMint);
         uint256 userbalance = token.balanceOf(msg.sender);
-        require(userbalance >= amount, "Amount too small");
+        require(userbalance >= bridged, "Amount too small");
 
-        token.safeTransferFrom(msg.sender, address(this), amount);
+        token.safeTransferFrom(msg.sender, address(this), bridged);
 
-        emit EthDeposit(msg.sender, sourceChainId, destChainId, destChainAddr, destChainMintAddr, tokenMint, amount, block.timestamp, depositCount++);
+        emit EthDeposit(msg.sender, sourceChainId, destChainId, destChainAddr, destChainMintAddr, tokenMint, bridged, block.timestamp, depositCount++);
     }
 
     function processWithdrawal(
@@ -125,6 +140,10 @@ contract SolanaEVMBridge is Ownable {
         require(recipient != address(0), "Invalid recipient address");
         require(!usedNullifiers[proof.publicSignals[0]], "Nullifier already used");
         usedNullifiers[proof.publicSignals[0]] = true;
+        bytes32 key = depositKey(record.owner, record.depositId);
+        require(!cancelledDeposits[key], "Deposit cancelled");
+        require(!releasedDeposits[key], "Deposit already released");
+        releasedDeposits[key] = true;
         
         require(record.destChainMintAddr != address(0), "Invalid token contract");
         IERC20 token = IERC20(record.destChainMintAddr);
@@ -140,19 +159,42 @@ contract SolanaEVMBridge is Ownable {
             "Invalid ZK proof"
         );
 
+        // the record holds canonical units, scale them up to the token's decimals
+        uint256 scale = canonicalScale[record.destChainMintAddr];
+        require(scale != 0, "Token not registered");
+        uint256 amount = record.amount * scale;
+
         uint256 contractBalance = token.balanceOf(address(this));
-        require(contractBalance >= record.amount, "Insufficient contract balance");
+        require(contractBalance >= amount, "Insufficient contract balance");
         
-        token.safeTransfer(recipient, record.amount);
+        token.safeTransfer(recipient, amount);
         
         emit WithdrawalProcessed(
             record.depositId,
             recipient,
             record.destChainMintAddr,
-            record.amount
+            amount
         );
     }
 
+    /**
+     * @dev Deposit ids are a sequence per solana owner
+     */
+    function depositKey(string memory owner, uint256 depositId) public pure returns (bytes32) {
+        return keccak256(abi.encode(owner, depositId));
+    }
+
+    /**
+     * @dev Stop a relayed deposit whose release never landed from being released,
+     * so the relayer can reset it to Pending on solana and its owner refund it
+     */
+    function cancelDeposit(string calldata owner, uint256 depositId) external onlyAuthorized {
+        bytes32 key = depositKey(owner, depositId);
+        require(!releasedDeposits[key], "Deposit already released");
+        cancelledDeposits[key] = true;
+        emit DepositCancelled(owner, depositId);
+    }
+
     /**
      * @dev Map a base58 address to an Ethereum address
      */
@@ -167,6 +209,16 @@ contract SolanaEVMBridge is Ownable {
         tokenMapping[solanaMint] = tokenContract;
     }
 
+    /**
+     * @dev Register the canonical decimals of the links a token is bridged over,
+     * the smaller of its decimals and the Solana mint's
+     */
+    function setCanonicalDecimals(address tokenContract, uint8 canonicalDecimals) external onlyOwner {
+        uint8 tokenDecimals = IERC20Metadata(tokenContract).decimals();
+        require(canonicalDecimals <= tokenDecimals, "Canonical decimals above token decimals");
+        canonicalScale[tokenContract] = 10 ** (tokenDecimals - canonicalDecimals);
+    }
+
     // Admin functions
     function addAuthorizedRelayer(address relayer) external onlyOwner {
         authorizedRelayers[relayer] = true;
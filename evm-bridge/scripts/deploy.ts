@@ -34,6 +34,9 @@ export async function deploy() {
   ]);
   console.log("token", token.address);
 
+  // the solana mint also has 2 decimals, so the canonical unit is one BrTN cent
+  await bridge.write.setCanonicalDecimals([token.address, 2]);
+
   // mint tokens to bridge contract
   const mintAmount = parseEther(`${totalSupply/2n}`); //1M
   await token.write.mint([bridge.address, mintAmount]);
@@ -14,7 +14,7 @@ function loadRecordData() {
         owner: recordData.owner,
         sourceChainId: recordData.source_chain_id,
         destChainId: recordData.dest_chain_id,
-        destChainAddr: "0x"+Buffer.from(bs58.decode(recordData.dest_chain_addr)).toString('hex') as any,
+        destChainAddr: "0x"+Buffer.from(recordData.dest_chain_addr.slice(12)).toString('hex') as any,
         destChainMintAddr: "0x"+Buffer.from(bs58.decode(recordData.dest_chain_mint_addr)).toString('hex') as any,
         mint: recordData.mint,
         amount: recordData.amount,
@@ -37,7 +37,7 @@ import addressBook from "../../config/localhost_address_book.json";
 dotenv.config({ path: ".env.local" });
 // @ts-ignore
 import snarkjs from "snarkjs";
-import { solanaWithdraw } from "./sol-bridge";
+import { getChainConfig, resetRelayedDeposit, solanaWithdraw, updateDepositStatus } from "./sol-bridge";
 
 const app = express();
 const PORT = process.env.PORT || 3006;
@@ -73,15 +73,9 @@ function pubkeyToField(pubkey: string): string {
   }
 }
 
-function stringToField(str: string): string {
-  if (str.startsWith("0x")) {
-    return hexToField(str);
-  }
-
-  const encoder = new TextEncoder();
-  const bytes = encoder.encode(str);
-  const bn = new BN(bytes);
-  return bn.toString(10);
+// deposit records keep the raw recipient left-padded to 32 bytes
+function evmAddress(recordAddr: number[]): string {
+  return "0x" + Buffer.from(recordAddr.slice(12)).toString("hex");
 }
 
 function computePathIndices(leafIndex: number, levels: number): number[] {
@@ -112,7 +106,7 @@ function generateCircuitInputs(
   const stateRoot = hexToField(proofData.root);
   const amount = rawDepositRecord.amount.toString();
   const destChainId = rawDepositRecord.dest_chain_id.toString();
-  const destChainAddr = stringToField(rawDepositRecord.dest_chain_addr);
+  const destChainAddr = new BN(rawDepositRecord.dest_chain_addr).toString(10);
 
   const accountHash = hexToField(proofData.hash.toString());
   const leafIndex = proofData.leafIndex.toString();
@@ -195,9 +189,7 @@ async function createProof(circuitInputs: CircuitInputs): Promise<any> {
 }
 
 async function getTokenBalance(rawDepositRecord: any) {
-  const receiverAddr =
-    "0x" +
-    Buffer.from(bs58.decode(rawDepositRecord.dest_chain_addr)).toString("hex");
+  const receiverAddr = evmAddress(rawDepositRecord.dest_chain_addr);
   // Use the actual deployed token contract address from address book
   const tokenContractAddr = addressBook.tokenSmartContractAddress;
   const bridgeTokenContract = new ethers.Contract(
@@ -210,17 +202,13 @@ async function getTokenBalance(rawDepositRecord: any) {
   return receiverBalance;
 }
 
-async function withdrawFromEthChain(rawDepositRecord: any, proof: any) {
+async function withdrawFromEthChain(address: string, rawDepositRecord: any, proof: any) {
   try {
     const contractWithSigner = evmBridgeContract.connect(wallet);
 
     console.log("Getting balance before withdrawal...");
     await getTokenBalance(rawDepositRecord);
-    const destChainAddr =
-      "0x" +
-      Buffer.from(bs58.decode(rawDepositRecord.dest_chain_addr)).toString(
-        "hex"
-      );
+    const destChainAddr = evmAddress(rawDepositRecord.dest_chain_addr);
     const destChainMintAddr = addressBook.tokenSmartContractAddress;
     const depositRecord = {
       owner: rawDepositRecord.owner.toString(),
@@ -235,10 +223,29 @@ async function withdrawFromEthChain(rawDepositRecord: any, proof: any) {
     };
 
     // Cast to any to avoid type-mismatch complaints in the generated typings
-    const tx = await (contractWithSigner as any).processWithdrawal(depositRecord, proof);
-    console.log("tx.hash:", tx.hash);
+    const unsignedTx = await wallet.populateTransaction(
+      await (contractWithSigner as any).processWithdrawal.populateTransaction(depositRecord, proof)
+    );
+    const signedTx = await wallet.signTransaction(unsignedTx);
+    const txHash = ethers.Transaction.from(signedTx).hash;
+    console.log("tx.hash:", txHash);
+
+    // the deposit has to be Relayed before the withdrawal can land on the EVM
+    // side, otherwise its owner could still refund it on solana
+    await updateDepositStatus(address, "relayed", txHash);
 
-    const receipt = await tx.wait();
+    let receipt;
+    try {
+      const tx = await provider.broadcastTransaction(signedTx);
+      receipt = await tx.wait();
+    } catch (error) {
+      // the release didn't land: cancel it on the EVM side first, so the
+      // signed transaction can't land later, then hand the deposit back
+      const cancelTx = await (contractWithSigner as any).cancelDeposit(depositRecord.owner, depositRecord.depositId);
+      await cancelTx.wait();
+      await resetRelayedDeposit(address, cancelTx.hash);
+      throw error;
+    }
     console.log("receipt:", receipt);
     await getTokenBalance(rawDepositRecord);
     return receipt;
@@ -275,6 +282,12 @@ evmBridgeContract.on(
         depositId,
       };
       console.log("depositEvent", depositEvent);
+
+      // act only once the deposit has as many confirmations as its chain's config asks for
+      const { confirmations } = await getChainConfig(Number(sourceChainId));
+      const eventPayload = args[args.length - 1];
+      await provider.waitForTransaction(eventPayload.log.transactionHash, confirmations);
+
       const depositEventArr = [
         depositor,
         sourceChainId,
@@ -391,6 +404,12 @@ export async function handleSolDeposit(address) {
 
     console.log("Deposit record decoded:", rawDepositRecord);
 
+    // refund_deposit already returned these tokens on solana
+    if (!("pending" in rawDepositRecord.status)) {
+      console.log("Deposit is not pending, skipping:", rawDepositRecord.status);
+      return;
+    }
+
     const maxRetries = 5;
     let proofData;
     for (let attempt = 0; attempt < maxRetries; attempt++) {
@@ -427,7 +446,8 @@ export async function handleSolDeposit(address) {
     const proof = await createProof(circuitInputs);
     console.log("Proof generated successfully", proof);
 
-    const withdrawRes = await withdrawFromEthChain(rawDepositRecord, proof);
+    const withdrawRes = await withdrawFromEthChain(address, rawDepositRecord, proof);
+    await updateDepositStatus(address, "finalized", withdrawRes.hash);
 
     console.log("all good")
 
@@ -1,5 +1,5 @@
 import fs from "fs";
-import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID } from "@solana/spl-token";
 import * as anchor from "@coral-xyz/anchor";
 import { CrossChainTokenBridge } from "../../sol-bridge/target/types/cross_chain_token_bridge";
 import idl from "../../sol-bridge/target/idl/cross_chain_token_bridge.json";
@@ -20,6 +20,7 @@ import { bs58 } from "@coral-xyz/anchor/dist/cjs/utils/bytes";
 import { BN } from "bn.js";
 import snarkjs from "snarkjs";
 import { handleSolDeposit } from "./server";
+import { deriveLinkHash } from "../../sol-bridge/lib/bridge";
 
 // globals
 const provider = anchor.AnchorProvider.env();
@@ -41,6 +42,103 @@ async function init() {
   initialised = true;
 }
 
+// vaults and liquidity pools are seeded by the hash of the link leaving
+// solana, so both directions of one mint pair share them
+function vaultLinkPda(seed: string, vaultLinkHash: string) {
+  return anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from(seed), Buffer.from(vaultLinkHash)],
+    program.programId
+  )[0];
+}
+
+function u32Le(value: number) {
+  const bytes = Buffer.alloc(4);
+  bytes.writeUInt32LE(value);
+  return bytes;
+}
+
+// registry entry of a chain: how many confirmations to wait for and how its
+// withdrawals are verified
+export async function getChainConfig(chainId: number) {
+  const chainConfig = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("chain_config"), u32Le(chainId)],
+    program.programId
+  )[0];
+  return program.account.chainConfig.fetch(chainConfig);
+}
+
+// Light system accounts and bridge-wide accounts, looked up instead of listed
+// so withdraw_with_proof fits in a single transaction. The table is created once
+// and its address kept next to the nullifier tree, so restarts reuse it.
+const withdrawLookupTableFile = "./withdrawLookupTable.json";
+let withdrawLookupTable: anchor.web3.AddressLookupTableAccount | undefined;
+
+async function getWithdrawLookupTable(addresses: anchor.web3.PublicKey[]) {
+  if (!withdrawLookupTable && fs.existsSync(withdrawLookupTableFile)) {
+    const { address } = JSON.parse(fs.readFileSync(withdrawLookupTableFile, "utf8"));
+    // null when the table is gone, e.g. after a validator reset
+    withdrawLookupTable = (
+      await rpc.getAddressLookupTable(new anchor.web3.PublicKey(address))
+    ).value ?? undefined;
+  }
+  if (!withdrawLookupTable) {
+    const [createIx, lookupTable] =
+      anchor.web3.AddressLookupTableProgram.createLookupTable({
+        authority: relayerKp.publicKey,
+        payer: relayerKp.publicKey,
+        recentSlot: await rpc.getSlot("finalized"),
+      });
+    await sendV0([createIx]);
+    fs.writeFileSync(
+      withdrawLookupTableFile,
+      JSON.stringify({ address: lookupTable.toBase58() })
+    );
+    withdrawLookupTable = (await rpc.getAddressLookupTable(lookupTable)).value;
+  }
+  const known = new Set(
+    withdrawLookupTable.state.addresses.map((a) => a.toBase58())
+  );
+  const missing = addresses
+    .filter((a, i) => addresses.findIndex((b) => b.equals(a)) === i)
+    .filter((a) => !known.has(a.toBase58()));
+  // a single extend instruction only fits about 30 addresses
+  for (let i = 0; i < missing.length; i += 20) {
+    await sendV0([
+      anchor.web3.AddressLookupTableProgram.extendLookupTable({
+        lookupTable: withdrawLookupTable.key,
+        authority: relayerKp.publicKey,
+        payer: relayerKp.publicKey,
+        addresses: missing.slice(i, i + 20),
+      }),
+    ]);
+  }
+  if (missing.length > 0) {
+    // new entries are only usable from the slot after they were added
+    const slot = await rpc.getSlot();
+    while ((await rpc.getSlot()) <= slot) await sleep(400);
+    withdrawLookupTable = (
+      await rpc.getAddressLookupTable(withdrawLookupTable.key)
+    ).value;
+  }
+  return withdrawLookupTable;
+}
+
+async function sendV0(
+  instructions: anchor.web3.TransactionInstruction[],
+  lookupTables: anchor.web3.AddressLookupTableAccount[] = []
+) {
+  const message = new anchor.web3.TransactionMessage({
+    payerKey: relayerKp.publicKey,
+    recentBlockhash: (await rpc.getLatestBlockhash()).blockhash,
+    instructions,
+  }).compileToV0Message(lookupTables);
+  const tx = new anchor.web3.VersionedTransaction(message);
+  tx.sign([relayerKp]);
+  const sig = await rpc.sendTransaction(tx);
+  await rpc.confirmTransaction(sig, "finalized");
+  return sig;
+}
+
 export async function solanaWithdraw(proofProc: any, depositEvent: any) {
   const stateTreeInfos = await rpc.getStateTreeInfos();
   const outputMerkleTree = stateTreeInfos[0].tree;
@@ -56,36 +154,10 @@ export async function solanaWithdraw(proofProc: any, depositEvent: any) {
     Buffer.from(depositEvent.depositor.replace("0x", ""), "hex")
   );
 
-  // initWithdrawalProofAccount
-  const withdrawalProofTx = await program.methods
-    .initWithdrawalProofAccount(
-      bn(depositEvent.depositId.toString()),
-      proofProc.proofA,
-      proofProc.proofB,
-      proofProc.proofC,
-      proofProc.publicSignals[0],
-      proofProc.publicSignals[1]
-    )
-    .accounts({
-      signer: relayerKp.publicKey,
-    })
-    .signers([relayerKp])
-    .transaction();
-
-  withdrawalProofTx.recentBlockhash = (
-    await rpc.getRecentBlockhash()
-  ).blockhash;
-  withdrawalProofTx.sign(relayerKp);
-
-  const sign = await rpc.sendTransaction(withdrawalProofTx, [relayerKp]);
-  await rpc.confirmTransaction(sign);
-  console.log("initWithdrawalProofAccount instr sign:", sign);
-
   // withdraw instruction
   const withdrawalRecordAccountSeed = deriveAddressSeed(
     [
       new TextEncoder().encode("withdrawal"),
-      new anchor.web3.PublicKey(depositEvent.destChainAddr).toBytes(),
       proofProc.publicSignals[0],
     ],
     new anchor.web3.PublicKey(program.idl.address)
@@ -133,31 +205,114 @@ export async function solanaWithdraw(proofProc: any, depositEvent: any) {
     .digest("hex")
     .slice(0, 16);
   console.log("linkHash", linkHash);
-  let tx = await program.methods
-    .withdraw(
+  // withdraws pay out of the vault of the link in the other direction
+  const vaultLinkHash = require("crypto")
+    .createHash("sha256")
+    .update(`${depositEvent.destChainId}_${depositEvent.destChainMintAddr}_${depositEvent.sourceChainId}_${depositEvent.tokenMint}`)
+    .digest("hex")
+    .slice(0, 16);
+  const vkVersion = Number(process.env.ETH_DEPOSIT_VK_VERSION || 1);
+  // the source chain's verifier names the circuit the proof is checked against
+  const sourceChainConfig = await getChainConfig(Number(depositEvent.sourceChainId));
+  const verifyingKey = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("vk"), u32Le(sourceChainConfig.verifier.groth16.circuitId), u32Le(vkVersion)],
+    program.programId
+  )[0];
+  const mint = new anchor.web3.PublicKey(depositEvent.destChainMintAddr);
+  const withdrawAccounts = {
+    relayer: relayerKp.publicKey,
+    verifyingKey,
+    recipient: depositEvent.destChainAddr,
+    mint,
+    tokenVault: vaultLinkPda("vault", vaultLinkHash),
+    liquidityPool: vaultLinkPda("pool", vaultLinkHash),
+    tokenProgram: TOKEN_PROGRAM_ID,
+  };
+  const lightAccounts = remainingAccounts.toAccountMetas().remainingAccounts;
+
+  // public signals: nullifier, new_root, sourceChainId, recipient, mint, tokenMint, amount, old_root
+  const withdrawIx = await program.methods
+    .withdrawWithProof(
       proof,
       packedAddressMerkleContext,
       outputMerkleTreeIndex,
       bn(depositEvent.amount.toString()),
       linkHash,
-      Buffer.from(proofProc.publicSignals[0])
+      Buffer.from(proofProc.publicSignals[0]),
+      vkVersion,
+      proofProc.proofA,
+      proofProc.proofB,
+      proofProc.proofC,
+      proofProc.publicSignals[7],
+      proofProc.publicSignals[1],
     )
-    .accounts({
-      relayer: relayerKp.publicKey,
-      recipient: depositEvent.destChainAddr,
-      mint: new anchor.web3.PublicKey(depositEvent.destChainMintAddr),
-      tokenProgram: TOKEN_PROGRAM_ID,
-    })
-    .preInstructions([computeBudgetIx])
-    .remainingAccounts(remainingAccounts.toAccountMetas().remainingAccounts)
-    .signers([relayerKp])
-    .transaction();
-
-  tx.recentBlockhash = (await rpc.getRecentBlockhash()).blockhash;
-  tx.sign(relayerKp);
-
-  const sig = await rpc.sendTransaction(tx, [relayerKp]);
-  await rpc.confirmTransaction(sig, "finalized");
+    .accounts({ withdraw: withdrawAccounts })
+    .remainingAccounts(lightAccounts)
+    .instruction();
+
+  const bridgeWideAccounts = ["bridge_state", "mint_authority", "__event_authority"].map(
+    (seed) =>
+      anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from(seed)],
+        program.programId
+      )[0]
+  );
+  const lookupTable = await getWithdrawLookupTable([
+    ...lightAccounts.map((a) => a.pubkey),
+    ...bridgeWideAccounts,
+    TOKEN_PROGRAM_ID,
+    ASSOCIATED_TOKEN_PROGRAM_ID,
+    anchor.web3.SystemProgram.programId,
+  ]);
+
+  let sig: string;
+  try {
+    sig = await sendV0([computeBudgetIx, withdrawIx], [lookupTable]);
+    console.log("withdrawWithProof instr sign:", sig);
+  } catch (err) {
+    // only fall back to storing the proof first when the single
+    // transaction does not fit, any other failure is final
+    if (!(err instanceof RangeError) && !/too large/i.test(String(err))) {
+      throw err;
+    }
+    console.log("withdrawWithProof too large, storing the proof first");
+    const withdrawalProofIx = await program.methods
+      .initWithdrawalProofAccount(
+        bn(depositEvent.depositId.toString()),
+        proofProc.proofA,
+        proofProc.proofB,
+        proofProc.proofC,
+        proofProc.publicSignals[0],
+        proofProc.publicSignals[7],
+        proofProc.publicSignals[1]
+      )
+      .accounts({
+        signer: relayerKp.publicKey,
+      })
+      .instruction();
+    const proofSign = await sendV0([withdrawalProofIx]);
+    console.log("initWithdrawalProofAccount instr sign:", proofSign);
+
+    const withdrawalProof = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("withdrawal_proof"), relayerKp.publicKey.toBuffer(), Buffer.from(proofProc.publicSignals[0])],
+      program.programId
+    )[0];
+    const twoStepIx = await program.methods
+      .withdraw(
+        proof,
+        packedAddressMerkleContext,
+        outputMerkleTreeIndex,
+        bn(depositEvent.amount.toString()),
+        linkHash,
+        Buffer.from(proofProc.publicSignals[0]),
+        vkVersion,
+      )
+      .accounts({ withdraw: withdrawAccounts, withdrawalProof })
+      .remainingAccounts(lightAccounts)
+      .instruction();
+    sig = await sendV0([computeBudgetIx, twoStepIx], [lookupTable]);
+    console.log("withdraw instr sign:", sig);
+  }
 
   const withdrawalRecordAccount = await rpc.getCompressedAccount(
     bn(withdrawalAccountAddress.toBytes())
@@ -171,13 +326,174 @@ export async function solanaWithdraw(proofProc: any, depositEvent: any) {
   return withdrawalRecord;
 }
 
-program.addEventListener("depositEvent", async (event: { address: number[] }) => {
-  console.log("event - depositEvent", event);
-  const depositAddress = new anchor.web3.PublicKey(new Uint8Array(event.address));
-  await sleep(4000);
-  console.log("depositAddress", depositAddress);
-  await handleSolDeposit(depositAddress.toBase58());
-});
+// Marks a solana deposit as relayed or finalized on its compressed record,
+// with the EVM transaction that released it.
+// the deposit record plus what the program needs to rewrite it
+async function depositRecordUpdate(depositAddress: string) {
+  const depositAccount = await rpc.getCompressedAccount(
+    bn(new anchor.web3.PublicKey(depositAddress).toBytes())
+  );
+  const depositRecord = program.coder.types.decode(
+    "depositRecordCompressedAccount",
+    depositAccount.data.data
+  );
+  const proofRes = await rpc.getValidityProofV0(
+    [
+      {
+        hash: depositAccount.hash,
+        tree: depositAccount.treeInfo.tree,
+        queue: depositAccount.treeInfo.queue,
+      },
+    ],
+    []
+  );
+  const systemAccountConfig = SystemAccountMetaConfig.new(program.programId);
+  let remainingAccounts =
+    PackedAccounts.newWithSystemAccounts(systemAccountConfig);
+  const merkleTreePubkeyIndex = remainingAccounts.insertOrGet(
+    depositAccount.treeInfo.tree
+  );
+  const queuePubkeyIndex = remainingAccounts.insertOrGet(
+    depositAccount.treeInfo.queue
+  );
+  const compressedAccountMeta = {
+    merkleContext: {
+      merkleTreePubkeyIndex,
+      queuePubkeyIndex,
+      leafIndex: depositAccount.leafIndex,
+      proveByIndex: false,
+    },
+    rootIndex: proofRes.rootIndices[0],
+    outputMerkleTreeIndex: merkleTreePubkeyIndex,
+    address: depositAccount.address,
+  };
+
+  return {
+    depositRecord,
+    proof: { 0: proofRes.compressedProof },
+    compressedAccountMeta,
+    remainingAccounts: remainingAccounts.toAccountMetas().remainingAccounts,
+  };
+}
+
+async function sendAsRelayer(tx: anchor.web3.Transaction) {
+  tx.recentBlockhash = (await rpc.getRecentBlockhash()).blockhash;
+  tx.sign(relayerKp);
+  const sig = await rpc.sendTransaction(tx, [relayerKp]);
+  await rpc.confirmTransaction(sig, "confirmed");
+  return sig;
+}
+
+export async function updateDepositStatus(
+  depositAddress: string,
+  status: "relayed" | "finalized",
+  destTxHash: string,
+) {
+  const { depositRecord, proof, compressedAccountMeta, remainingAccounts } =
+    await depositRecordUpdate(depositAddress);
+
+  const computeBudgetIx = anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({
+    units: 1000000,
+  });
+
+  let tx = await program.methods
+    .updateDepositStatus(
+      proof,
+      depositRecord,
+      compressedAccountMeta,
+      { [status]: {} } as any,
+      Array.from(Buffer.from(destTxHash.replace("0x", ""), "hex")),
+    )
+    .accounts({
+      relayer: relayerKp.publicKey,
+    })
+    .preInstructions([computeBudgetIx])
+    .remainingAccounts(remainingAccounts)
+    .signers([relayerKp])
+    .transaction();
+
+  const sig = await sendAsRelayer(tx);
+  console.log(`deposit ${depositAddress} marked ${status}`, sig);
+}
+
+// Puts a Relayed deposit back to Pending once its release was cancelled on
+// the EVM side, cancelTxHash, so the owner can refund it. The program only
+// allows it after the link's refund timeout.
+export async function resetRelayedDeposit(
+  depositAddress: string,
+  cancelTxHash: string,
+) {
+  const { depositRecord, proof, compressedAccountMeta, remainingAccounts } =
+    await depositRecordUpdate(depositAddress);
+  const linkHash = deriveLinkHash(
+    depositRecord.sourceChainId,
+    depositRecord.mint.toString(),
+    depositRecord.destChainId,
+    depositRecord.destChainMintAddr,
+  );
+
+  const computeBudgetIx = anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({
+    units: 1000000,
+  });
+
+  let tx = await program.methods
+    .resetRelayedDeposit(
+      proof,
+      linkHash,
+      depositRecord,
+      compressedAccountMeta,
+      Array.from(Buffer.from(cancelTxHash.replace("0x", ""), "hex")),
+    )
+    .accounts({
+      relayer: relayerKp.publicKey,
+    })
+    .preInstructions([computeBudgetIx])
+    .remainingAccounts(remainingAccounts)
+    .signers([relayerKp])
+    .transaction();
+
+  const sig = await sendAsRelayer(tx);
+  console.log(`deposit ${depositAddress} reset to pending`, sig);
+}
+
+// Events are emitted with emit_cpi!, as self-CPIs whose data is the event
+// instruction tag followed by the encoded event, so they never show up in
+// (possibly truncated) logs.
+const EVENT_IX_TAG = Buffer.from("e445a52e51cb9a1d", "hex");
+
+async function cpiEvents(signature: string) {
+  const tx = await rpc.getTransaction(signature, {
+    commitment: "confirmed",
+    maxSupportedTransactionVersion: 0,
+  });
+  if (!tx?.meta) return [];
+  const accountKeys = tx.transaction.message.getAccountKeys({
+    accountKeysFromLookups: tx.meta.loadedAddresses,
+  });
+  const events = [];
+  for (const inner of tx.meta.innerInstructions ?? []) {
+    for (const ix of inner.instructions) {
+      if (!accountKeys.get(ix.programIdIndex)?.equals(program.programId)) continue;
+      const data = Buffer.from(bs58.decode(ix.data));
+      if (!data.subarray(0, 8).equals(EVENT_IX_TAG)) continue;
+      const event = program.coder.events.decode(data.subarray(8).toString("base64"));
+      if (event) events.push(event);
+    }
+  }
+  return events;
+}
+
+rpc.onLogs(program.programId, async ({ signature, err }) => {
+  if (err) return;
+  for (const event of await cpiEvents(signature)) {
+    if (event.name !== "depositEvent") continue;
+    console.log("event - depositEvent", event.data);
+    const depositAddress = new anchor.web3.PublicKey(new Uint8Array(event.data.address));
+    await sleep(4000);
+    console.log("depositAddress", depositAddress);
+    await handleSolDeposit(depositAddress.toBase58());
+  }
+}, "confirmed");
 
 class PackedAccounts {
   private preAccounts: anchor.web3.AccountMeta[] = [];
@@ -2,7 +2,7 @@ export interface DepositRecord {
     owner: string;
     source_chain_id: number;
     dest_chain_id: number;
-    dest_chain_addr: string;
+    dest_chain_addr: number[];
     dest_chain_mint_addr: string;
     mint: string;
     amount: string;
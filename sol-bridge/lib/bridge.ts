@@ -0,0 +1,50 @@
+// Encodings shared by migrations/deploy.ts and tests/test.ts
+
+import { createHash } from "crypto";
+
+// solana's chain id, see state::SOURCE_CHAIN_ID
+const SOLANA_CHAIN_ID = 1;
+
+// token bridge seed: first 16 hex chars of sha256("{src}_{srcMint}_{dst}_{dstMint}")
+export function deriveLinkHash(
+  source_chain: number,
+  source_chain_mint_addr: string,
+  dest_chain_id: number,
+  dest_chain_mint_addr: string,
+): string {
+  return createHash("sha256")
+    .update(`${source_chain}_${source_chain_mint_addr}_${dest_chain_id}_${dest_chain_mint_addr}`)
+    .digest("hex")
+    .slice(0, 16);
+}
+
+// vaults, liquidity pools and LP mints are seeded by the hash of the link
+// leaving solana, so both directions of one mint pair share them
+export function deriveVaultLinkHash(
+  source_chain: number,
+  source_chain_mint_addr: string,
+  dest_chain_id: number,
+  dest_chain_mint_addr: string,
+): string {
+  return source_chain === SOLANA_CHAIN_ID
+    ? deriveLinkHash(source_chain, source_chain_mint_addr, dest_chain_id, dest_chain_mint_addr)
+    : deriveLinkHash(dest_chain_id, dest_chain_mint_addr, source_chain, source_chain_mint_addr);
+}
+
+// snarkjs G1 point -> x || y, G2 point -> x_c1 || x_c0 || y_c1 || y_c0, all 32-byte big-endian
+export function g1ToBytes(point: string[]): number[] {
+  return [...fieldToBytes(point[0]), ...fieldToBytes(point[1])];
+}
+
+export function g2ToBytes(point: string[][]): number[] {
+  return [
+    ...fieldToBytes(point[0][1]),
+    ...fieldToBytes(point[0][0]),
+    ...fieldToBytes(point[1][1]),
+    ...fieldToBytes(point[1][0]),
+  ];
+}
+
+export function fieldToBytes(value: string): number[] {
+  return Array.from(Buffer.from(BigInt(value).toString(16).padStart(64, "0"), "hex"));
+}
@@ -22,6 +22,14 @@ import { createRpc } from "@lightprotocol/stateless.js";
 import { BN } from "bn.js";
 import addressBook from "../../config/localhost_address_book.json";
 import { bs58 } from "@coral-xyz/anchor/dist/cjs/utils/bytes";
+import { deriveLinkHash, deriveVaultLinkHash, fieldToBytes, g1ToBytes, g2ToBytes } from "../lib/bridge";
+
+// root of the relayer's empty nullifier tree: the depth-32 indexed merkle tree
+// holding only its (0, 0) sentinel leaf
+const EMPTY_NULLIFIER_ROOT = fieldToBytes("20904263309775236245566347617139780413277211320889904019085937429497372345062");
+
+// circuit id the eth deposit keys are registered under and EVM chain configs verify with
+const ETH_DEPOSIT_CIRCUIT_ID = 1;
 
 function toBigInt(amount: number | bigint): bigint {
   return typeof amount === "bigint" ? amount : BigInt(amount);
@@ -95,30 +103,87 @@ async function main() {
   anchor.setProvider(provider)
   const program = new anchor.Program(idl as CrossChainTokenBridge, provider);
   const rpc = createRpc(process.env.SOLANA_VALIDATOR_URL,process.env.SOLANA_COMPRESSION_API_ENDPOINT,process.env.SOLANA_PROVER_ENDPOINT);
-  const depositToVaultTx = await program.methods.depositToVault(new BN(mintAmount))
+  // `init` is gated on the program's upgrade authority, which deploys with keys/signer.json
+  const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111"),
+  );
+  // a new bridge starts from the empty nullifier tree, so the relayer's saved
+  // tree from a previous deployment is set aside rather than reused
+  const relayerTreePath = path.join(__dirname, "../../relayer-ts/ethDepositIMT.json");
+  if (fs.existsSync(relayerTreePath)) {
+    fs.renameSync(relayerTreePath, `${relayerTreePath}.${Date.now()}.bak`);
+  }
+  const initBridgeStatePdaTx = await program.methods.init(EMPTY_NULLIFIER_ROOT)
   .accounts({
     signer: signer.publicKey,
-    mint: mintPubkey,
-    tokenProgram: TOKEN_PROGRAM_ID
+    programData,
   })
   .signers([signer])
   .transaction();
+  initBridgeStatePdaTx.recentBlockhash = (await rpc.getRecentBlockhash()).blockhash;
+  const sign2 = await rpc.sendTransaction(initBridgeStatePdaTx, [signer]);
+  await rpc.confirmTransaction(sign2);
 
-  depositToVaultTx.recentBlockhash = (await rpc.getRecentBlockhash()).blockhash;
-  const sign = await rpc.sendTransaction(depositToVaultTx, [signer]);
-  await rpc.confirmTransaction(sign);
-
-  // 2 - by here sol bridge has enough tokens to withdraw to the users
+  // the relayer reports deposit status back on the deposit records
+  const relayerKp = Keypair.fromSecretKey(
+    new Uint8Array(
+      JSON.parse(
+        fs.readFileSync(path.join(__dirname, "../../relayer-ts/relayer.json"), "utf8")
+      )
+    )
+  );
+  const setRelayerTx = await program.methods.setRelayer(relayerKp.publicKey)
+  .accounts({
+    admin: signer.publicKey
+  })
+  .signers([signer])
+  .transaction();
+  setRelayerTx.recentBlockhash = (await rpc.getRecentBlockhash()).blockhash;
+  const relayerSign = await rpc.sendTransaction(setRelayerTx, [signer]);
+  await rpc.confirmTransaction(relayerSign);
 
-  const initBridgeStatePdaTx = await program.methods.init()
+  // withdrawals from EVM chains verify against the eth deposit circuit's key; the
+  // relayer picks the version up from the same ETH_DEPOSIT_VK_VERSION
+  const vkVersion = Number(process.env.ETH_DEPOSIT_VK_VERSION || 1);
+  const vk = JSON.parse(
+    fs.readFileSync(path.join(__dirname, "../../circom/ethDepositProof_js/verification_key.json"), "utf8")
+  );
+  const ic = vk.IC.map(g1ToBytes);
+  const registerVkTx = await program.methods.registerVerifyingKey(
+    ETH_DEPOSIT_CIRCUIT_ID,
+    vkVersion,
+    vk.nPublic,
+    g1ToBytes(vk.vk_alpha_1),
+    g2ToBytes(vk.vk_beta_2),
+    g2ToBytes(vk.vk_gamma_2),
+    g2ToBytes(vk.vk_delta_2),
+    ic.slice(0, 2),
+  )
   .accounts({
-    signer: signer.publicKey
+    admin: signer.publicKey
   })
   .signers([signer])
   .transaction();
-  initBridgeStatePdaTx.recentBlockhash = (await rpc.getRecentBlockhash()).blockhash;
-  const sign2 = await rpc.sendTransaction(initBridgeStatePdaTx, [signer]);
-  await rpc.confirmTransaction(sign2);
+  // the rest of the IC goes in its own transaction to stay under the size limit
+  const appendVkIcTx = await program.methods.appendVerifyingKeyIc(ETH_DEPOSIT_CIRCUIT_ID, vkVersion, ic.slice(2))
+  .accounts({
+    admin: signer.publicKey
+  })
+  .signers([signer])
+  .transaction();
+  const activateVkTx = await program.methods.activateVerifyingKey(ETH_DEPOSIT_CIRCUIT_ID, vkVersion)
+  .accounts({
+    admin: signer.publicKey
+  })
+  .signers([signer])
+  .transaction();
+  for (const tx of [registerVkTx, appendVkIcTx, activateVkTx]) {
+    tx.recentBlockhash = (await rpc.getRecentBlockhash()).blockhash;
+    const vkSign = await rpc.sendTransaction(tx, [signer]);
+    await rpc.confirmTransaction(vkSign);
+  }
+  console.log("Registered eth deposit verifying key version", vkVersion);
 
   // 3 - by here token bridge is initialised
   const toBs58 = x => bs58.encode(Buffer.from(x.replace("0x", ""), "hex"));
@@ -127,16 +192,33 @@ async function main() {
     {mintAddr: toBs58(addressBook.tokenSmartContractAddress), chaindId: 31337},
     {mintAddr: toBs58(addressBook.tokenSmartContractAddress), chaindId: 11155111},
   ]
-  
+
+
+  for (const { chaindId } of CHAIN_IDS) {
+    const isSolana = chaindId === 1;
+    const setChainConfigTx = await program.methods.setChainConfig(
+      chaindId,
+      true,
+      isSolana ? { solana: {} } : { evm: {} },
+      isSolana ? 32 : 12,
+      isSolana ? { relayer: {} } : { groth16: { circuitId: ETH_DEPOSIT_CIRCUIT_ID } },
+    )
+    .accounts({
+      admin: signer.publicKey
+    })
+    .signers([signer])
+    .transaction();
+    setChainConfigTx.recentBlockhash = (await rpc.getRecentBlockhash()).blockhash;
+    const chainSign = await rpc.sendTransaction(setChainConfigTx, [signer]);
+    await rpc.confirmTransaction(chainSign);
+  }
+
   for (let i = 0; i < CHAIN_IDS.length; i++) {
     for (let j = 0; j < CHAIN_IDS.length; j++) {
       if (i === j) continue;
       const link = `${CHAIN_IDS[i].chaindId}_${CHAIN_IDS[i].mintAddr}_${CHAIN_IDS[j].chaindId}_${CHAIN_IDS[j].mintAddr}`;
       console.log('link',link);
-      const linkHash = await require('crypto').createHash('sha256')
-      .update(link)
-      .digest('hex')
-      .slice(0, 16);
+      const linkHash = deriveLinkHash(CHAIN_IDS[i].chaindId, CHAIN_IDS[i].mintAddr.toString(), CHAIN_IDS[j].chaindId, CHAIN_IDS[j].mintAddr.toString());
       
       console.log('link',linkHash);
       const initTokenBridgeTx = await program.methods.initTokenBridge(
@@ -144,10 +226,21 @@ async function main() {
         CHAIN_IDS[i].mintAddr.toString(),
         CHAIN_IDS[j].chaindId,
         CHAIN_IDS[j].mintAddr.toString(),
-        linkHash
+        // the solana mint is native, liquidity comes from deposit_to_vault above
+        { lockRelease: {} },
+        // BridgeToken on the EVM side uses the same 2 decimals as the mint above
+        decimals,
+        decimals,
       )
       .accounts({
-        signer: signer.publicKey
+        signer: signer.publicKey,
+        // seeded by the link hash the program derives from the four args above
+        tokenBridge: anchor.web3.PublicKey.findProgramAddressSync(
+          [Buffer.from("tb"), Buffer.from(linkHash)],
+          program.programId
+        )[0],
+        // links between two EVM chains have no solana mint
+        mint: CHAIN_IDS[i].chaindId === 1 ? CHAIN_IDS[i].mintAddr : CHAIN_IDS[j].chaindId === 1 ? CHAIN_IDS[j].mintAddr : null,
       })
       .signers([signer])
       .transaction();
@@ -157,6 +250,43 @@ async function main() {
       await rpc.confirmTransaction(bridgeSign);
       
       console.log(`Initialized token bridge from chain ${CHAIN_IDS[i].chaindId} to ${CHAIN_IDS[j].chaindId}`);
+
+      // withdrawals into solana are paid from the vault shared with the reverse link
+      if (CHAIN_IDS[j].chaindId === 1) {
+        // seeded by the hash of the reverse link, the one leaving solana
+        const vaultLinkHash = deriveVaultLinkHash(CHAIN_IDS[i].chaindId, CHAIN_IDS[i].mintAddr.toString(), CHAIN_IDS[j].chaindId, CHAIN_IDS[j].mintAddr.toString());
+        const tokenVault = anchor.web3.PublicKey.findProgramAddressSync(
+          [Buffer.from("vault"), Buffer.from(vaultLinkHash)],
+          program.programId
+        )[0];
+        const liquidityPool = anchor.web3.PublicKey.findProgramAddressSync(
+          [Buffer.from("pool"), Buffer.from(vaultLinkHash)],
+          program.programId
+        )[0];
+        const lpMint = anchor.web3.PublicKey.findProgramAddressSync(
+          [Buffer.from("lp_mint"), Buffer.from(vaultLinkHash)],
+          program.programId
+        )[0];
+        const depositToVaultTx = await program.methods.depositToVault(
+          linkHash,
+          new BN((mintAmount / BigInt(CHAIN_IDS.length - 1)).toString())
+        )
+        .accounts({
+          signer: signer.publicKey,
+          mint: mintPubkey,
+          tokenVault,
+          liquidityPool,
+          lpMint,
+          tokenProgram: TOKEN_PROGRAM_ID
+        })
+        .signers([signer])
+        .transaction();
+
+        depositToVaultTx.recentBlockhash = (await rpc.getRecentBlockhash()).blockhash;
+        const sign = await rpc.sendTransaction(depositToVaultTx, [signer]);
+        await rpc.confirmTransaction(sign);
+        console.log(`Provided liquidity for withdrawals from chain ${CHAIN_IDS[i].chaindId}`);
+      }
     }
   }
 
@@ -0,0 +1,191 @@
+// Checks the snarkjs verification keys under circom/*_js against their
+// circuits and generates a test per circuit that verifies the proof.json /
+// public.json fixtures next to each key. Keys reach the program through
+// register_verifying_key, not through the binary.
+
+use std::{env, fmt::Write as _, fs, path::{Path, PathBuf}};
+
+use serde_json::Value;
+
+// BN254 base field modulus, big-endian
+const BN254_BASE_FIELD: [u8; 32] = [
+    48,100,78,114,225,49,160,41,184,80,69,182,129,129,88,93,
+    151,129,106,145,104,113,202,141,60,32,140,22,216,124,253,71,
+];
+
+fn main() {
+    let circom_dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap())
+        .join("../../../circom")
+        .canonicalize()
+        .expect("circom directory next to sol-bridge");
+    println!("cargo:rerun-if-changed={}", circom_dir.display());
+
+    let mut circuit_dirs: Vec<PathBuf> = fs::read_dir(&circom_dir)
+        .unwrap_or_else(|e| panic!("reading {}: {e}", circom_dir.display()))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.join("verification_key.json").is_file())
+        .collect();
+    circuit_dirs.sort();
+
+    let mut fixtures = String::from("// @generated by build.rs from circom/*_js/{verification_key,proof,public}.json\n\n");
+
+    for dir in &circuit_dirs {
+        let vk_path = dir.join("verification_key.json");
+        println!("cargo:rerun-if-changed={}", vk_path.display());
+
+        let circuit = dir.file_name().unwrap().to_str().unwrap().trim_end_matches("_js").to_string();
+        let vk = read_json(&vk_path);
+
+        assert_ne!(
+            vk["vk_gamma_2"],
+            vk["vk_delta_2"],
+            "{}: vk_delta_2 equals vk_gamma_2, the setup had no phase-2 contribution and proofs can be forged, regenerate it with `make {circuit}` in circom/",
+            vk_path.display()
+        );
+
+        let circuit_source = circom_dir.join(format!("{circuit}.circom"));
+        if let Some(expected) = count_public_signals(&circuit_source) {
+            println!("cargo:rerun-if-changed={}", circuit_source.display());
+            let n_public = vk["nPublic"].as_u64().unwrap() as usize;
+            // a stale key rejects every proof of the current circuit
+            assert_eq!(
+                n_public,
+                expected,
+                "{}: nPublic is {n_public} but {} declares {expected} public signals, regenerate it with `make {circuit}` in circom/",
+                vk_path.display(),
+                circuit_source.display()
+            );
+        }
+
+        let proof_path = dir.join("proof.json");
+        let public_path = dir.join("public.json");
+        if proof_path.is_file() && public_path.is_file() {
+            println!("cargo:rerun-if-changed={}", proof_path.display());
+            println!("cargo:rerun-if-changed={}", public_path.display());
+            write_fixture_test(&mut fixtures, &circuit, &vk, &read_json(&proof_path), &read_json(&public_path));
+        }
+    }
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    fs::write(out_dir.join("verifying_key_fixtures.rs"), fixtures).unwrap();
+}
+
+fn read_json(path: &Path) -> Value {
+    let data = fs::read_to_string(path).unwrap_or_else(|e| panic!("reading {}: {e}", path.display()));
+    serde_json::from_str(&data).unwrap_or_else(|e| panic!("parsing {}: {e}", path.display()))
+}
+
+fn write_fixture_test(out: &mut String, circuit: &str, vk: &Value, proof: &Value, public: &Value) {
+    let public = public.as_array().unwrap();
+
+    // groth16-solana expects proof_a negated
+    let mut proof_a = g1_bytes(&proof["pi_a"]);
+    let y: [u8; 32] = proof_a[32..].try_into().unwrap();
+    proof_a[32..].copy_from_slice(&negate(y));
+
+    let ic = vk["IC"].as_array().unwrap();
+
+    writeln!(out, "#[test]\nfn {}_fixture_verifies() {{", snake_case(circuit)).unwrap();
+    writeln!(out, "    let vk = Groth16Verifyingkey {{").unwrap();
+    writeln!(out, "        nr_pubinputs: {},", ic.len()).unwrap();
+    writeln!(out, "        vk_alpha_g1: {},", bytes_literal(&g1_bytes(&vk["vk_alpha_1"]))).unwrap();
+    writeln!(out, "        vk_beta_g2: {},", bytes_literal(&g2_bytes(&vk["vk_beta_2"]))).unwrap();
+    writeln!(out, "        vk_gamme_g2: {},", bytes_literal(&g2_bytes(&vk["vk_gamma_2"]))).unwrap();
+    writeln!(out, "        vk_delta_g2: {},", bytes_literal(&g2_bytes(&vk["vk_delta_2"]))).unwrap();
+    writeln!(out, "        vk_ic: &[").unwrap();
+    for point in ic {
+        writeln!(out, "            {},", bytes_literal(&g1_bytes(point))).unwrap();
+    }
+    writeln!(out, "        ],\n    }};").unwrap();
+    writeln!(out, "    let proof_a: [u8; 64] = {};", bytes_literal(&proof_a)).unwrap();
+    writeln!(out, "    let proof_b: [u8; 128] = {};", bytes_literal(&g2_bytes(&proof["pi_b"]))).unwrap();
+    writeln!(out, "    let proof_c: [u8; 64] = {};", bytes_literal(&g1_bytes(&proof["pi_c"]))).unwrap();
+    writeln!(out, "    let public_inputs: [[u8; 32]; {}] = [", public.len()).unwrap();
+    for input in public {
+        writeln!(out, "        {},", bytes_literal(&field_bytes(input))).unwrap();
+    }
+    writeln!(out, "    ];").unwrap();
+    writeln!(out, "    groth16_verifier(proof_a, proof_b, proof_c, &public_inputs, vk).unwrap();\n}}\n").unwrap();
+}
+
+// Outputs of the main template plus the inputs listed in `component main {public [..]}`,
+// the order snarkjs puts them in public.json
+fn count_public_signals(path: &Path) -> Option<usize> {
+    let source = fs::read_to_string(path).ok()?;
+    let main = &source[source.find("component main")?..];
+    let template = main[main.find('=')? + 1..].trim_start();
+    let template = &template[..template.find('(')?];
+
+    let public_inputs = match main.find("public [") {
+        Some(start) => {
+            let list = &main[start + "public [".len()..];
+            list[..list.find(']')?].split(',').filter(|s| !s.trim().is_empty()).count()
+        }
+        None => 0,
+    };
+
+    let body = &source[source.find(&format!("template {template}("))?..];
+    let body = &body[..body[1..].find("\ntemplate ").map_or(body.len(), |end| end + 1)];
+    let outputs = body.matches("signal output").count();
+
+    Some(outputs + public_inputs)
+}
+
+fn g1_bytes(point: &Value) -> Vec<u8> {
+    [field_bytes(&point[0]), field_bytes(&point[1])].concat()
+}
+
+// snarkjs lists G2 coordinates as [c0, c1]; the alt_bn128 syscalls take c1 first
+fn g2_bytes(point: &Value) -> Vec<u8> {
+    [
+        field_bytes(&point[0][1]),
+        field_bytes(&point[0][0]),
+        field_bytes(&point[1][1]),
+        field_bytes(&point[1][0]),
+    ]
+    .concat()
+}
+
+fn field_bytes(value: &Value) -> [u8; 32] {
+    let decimal = value.as_str().unwrap();
+    let mut out = [0u8; 32];
+    for digit in decimal.bytes() {
+        let mut carry = (digit - b'0') as u32;
+        for byte in out.iter_mut().rev() {
+            let v = *byte as u32 * 10 + carry;
+            *byte = v as u8;
+            carry = v >> 8;
+        }
+        assert_eq!(carry, 0, "{decimal} does not fit in 32 bytes");
+    }
+    out
+}
+
+fn negate(y: [u8; 32]) -> [u8; 32] {
+    if y == [0u8; 32] {
+        return y;
+    }
+    let mut out = [0u8; 32];
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let v = BN254_BASE_FIELD[i] as i16 - y[i] as i16 - borrow;
+        borrow = (v < 0) as i16;
+        out[i] = (v + 256 * borrow) as u8;
+    }
+    out
+}
+
+fn bytes_literal(bytes: &[u8]) -> String {
+    let items: Vec<String> = bytes.iter().map(u8::to_string).collect();
+    format!("[{}]", items.join(","))
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            out.push('_');
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
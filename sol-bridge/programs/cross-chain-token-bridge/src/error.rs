@@ -15,5 +15,95 @@ pub enum ErrorCode {
     Groth16VerificationFailed,
 
     #[msg("Invalid args")]
-    InvalidArgs
-}
\ No newline at end of file
+    InvalidArgs,
+
+    #[msg("Unauthorized")]
+    Unauthorized,
+
+    #[msg("NoPendingAdmin")]
+    NoPendingAdmin,
+
+    #[msg("BridgePaused")]
+    BridgePaused,
+
+    #[msg("TokenBridgePaused")]
+    TokenBridgePaused,
+
+    #[msg("TransferAmountExceedsLimit")]
+    TransferAmountExceedsLimit,
+
+    #[msg("DepositVolumeLimitExceeded")]
+    DepositVolumeLimitExceeded,
+
+    #[msg("WithdrawVolumeLimitExceeded")]
+    WithdrawVolumeLimitExceeded,
+
+    #[msg("MathOverflow")]
+    MathOverflow,
+
+    #[msg("StaleNullifierRoot")]
+    StaleNullifierRoot,
+
+    #[msg("VerifyingKeyNotActive")]
+    VerifyingKeyNotActive,
+
+    #[msg("VerifyingKeyIncomplete")]
+    VerifyingKeyIncomplete,
+
+    #[msg("InvalidVerifyingKeyStatus")]
+    InvalidVerifyingKeyStatus,
+
+    #[msg("FeeExceedsAmount")]
+    FeeExceedsAmount,
+
+    #[msg("ChainNotEnabled")]
+    ChainNotEnabled,
+
+    #[msg("TokenVaultRequired")]
+    TokenVaultRequired,
+
+    #[msg("MintNotOwnedByBridge")]
+    MintNotOwnedByBridge,
+
+    #[msg("MintDecimalsMismatch")]
+    MintDecimalsMismatch,
+
+    #[msg("UnsupportedMintExtension")]
+    UnsupportedMintExtension,
+
+    #[msg("RefundsDisabled")]
+    RefundsDisabled,
+
+    #[msg("RefundTimeoutNotReached")]
+    RefundTimeoutNotReached,
+
+    #[msg("DepositNotPending")]
+    DepositNotPending,
+
+    #[msg("InvalidDepositStatusTransition")]
+    InvalidDepositStatusTransition,
+
+    #[msg("InsufficientLiquidity")]
+    InsufficientLiquidity,
+
+    #[msg("NoVaultForLink")]
+    NoVaultForLink,
+
+    #[msg("InvalidDestinationAddress")]
+    InvalidDestinationAddress,
+
+    #[msg("InvalidAddressChecksum")]
+    InvalidAddressChecksum,
+
+    #[msg("MintMismatch")]
+    MintMismatch,
+
+    #[msg("WithdrawalProofNotExpired")]
+    WithdrawalProofNotExpired,
+
+    #[msg("UnsupportedVerifier")]
+    UnsupportedVerifier,
+
+    #[msg("InvalidVerifyingKey")]
+    InvalidVerifyingKey,
+}
@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+use crate::{error::ErrorCode, state::{BridgeState, EVENT_VERSION}};
+
+#[event]
+pub struct AdminAcceptedEvent {
+    version: u8,
+    previous_admin: Pubkey,
+    admin: Pubkey,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptAdminContext<'info> {
+    pub new_admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"bridge_state"],
+        bump,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+}
+
+pub fn accept_admin_handler(ctx: Context<AcceptAdminContext>) -> Result<()> {
+    let bridge_state = &mut ctx.accounts.bridge_state;
+    let pending_admin = bridge_state.pending_admin.ok_or(ErrorCode::NoPendingAdmin)?;
+    require_keys_eq!(pending_admin, ctx.accounts.new_admin.key(), ErrorCode::Unauthorized);
+
+    let previous_admin = bridge_state.admin;
+    bridge_state.admin = pending_admin;
+    bridge_state.pending_admin = None;
+
+    emit_cpi!(AdminAcceptedEvent {
+        version: EVENT_VERSION,
+        previous_admin,
+        admin: pending_admin,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+use crate::{error::ErrorCode, instructions::VerifyingKeyStatusEvent, state::{BridgeState, VerifyingKey, VerifyingKeyStatus, EVENT_VERSION}};
+
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(circuit_id: u32, version: u32)]
+pub struct ActivateVerifyingKeyContext<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"bridge_state"],
+        bump,
+        has_one = admin @ ErrorCode::Unauthorized,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+
+    #[account(
+        mut,
+        seeds = [b"vk", circuit_id.to_le_bytes().as_ref(), version.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub verifying_key: Box<Account<'info, VerifyingKey>>,
+}
+
+pub fn activate_verifying_key_handler(
+    ctx: Context<ActivateVerifyingKeyContext>,
+    circuit_id: u32,
+    version: u32,
+) -> Result<()> {
+    let verifying_key = &mut ctx.accounts.verifying_key;
+    require!(verifying_key.status == VerifyingKeyStatus::Registered, ErrorCode::InvalidVerifyingKeyStatus);
+    require!(verifying_key.is_complete(), ErrorCode::VerifyingKeyIncomplete);
+
+    verifying_key.status = VerifyingKeyStatus::Active;
+
+    emit_cpi!(VerifyingKeyStatusEvent {
+        version: EVENT_VERSION,
+        circuit_id,
+        vk_version: version,
+        status: VerifyingKeyStatus::Active,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+use crate::{error::ErrorCode, state::{BridgeState, VerifyingKey, VerifyingKeyStatus, EVENT_VERSION}};
+
+#[event]
+pub struct VerifyingKeyIcAppendedEvent {
+    version: u8,
+    circuit_id: u32,
+    vk_version: u32,
+    // vk_ic entries stored so far, nr_public_inputs + 1 when complete
+    ic_len: u8,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(circuit_id: u32, version: u32)]
+pub struct AppendVerifyingKeyIcContext<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"bridge_state"],
+        bump,
+        has_one = admin @ ErrorCode::Unauthorized,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+
+    #[account(
+        mut,
+        seeds = [b"vk", circuit_id.to_le_bytes().as_ref(), version.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub verifying_key: Box<Account<'info, VerifyingKey>>,
+}
+
+pub fn append_verifying_key_ic_handler(
+    ctx: Context<AppendVerifyingKeyIcContext>,
+    circuit_id: u32,
+    version: u32,
+    vk_ic: Vec<[u8; 64]>,
+) -> Result<()> {
+    let verifying_key = &mut ctx.accounts.verifying_key;
+    // an activated key is immutable
+    require!(verifying_key.status == VerifyingKeyStatus::Registered, ErrorCode::InvalidVerifyingKeyStatus);
+    require!(
+        verifying_key.vk_ic.len() + vk_ic.len() <= verifying_key.nr_public_inputs as usize + 1,
+        ErrorCode::InvalidArgs
+    );
+    require!(vk_ic.iter().all(|point| *point != [0u8; 64]), ErrorCode::InvalidVerifyingKey);
+
+    verifying_key.vk_ic.extend(vk_ic);
+
+    emit_cpi!(VerifyingKeyIcAppendedEvent {
+        version: EVENT_VERSION,
+        circuit_id,
+        vk_version: version,
+        ic_len: ctx.accounts.verifying_key.vk_ic.len() as u8,
+    });
+
+    Ok(())
+}
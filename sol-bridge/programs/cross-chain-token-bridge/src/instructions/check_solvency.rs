@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+use crate::{error::ErrorCode, state::{TokenBridge, TokenBridgeMode, EVENT_VERSION}};
+
+#[event]
+pub struct SolvencyCheckEvent {
+    version: u8,
+    token_bridge: Pubkey,
+    // None when the link has no reverse link sharing its vault
+    reverse_token_bridge: Option<Pubkey>,
+    mint: Pubkey,
+    vault_balance: u64,
+    // locked + liquidity_provided - released over both links
+    expected_balance: i128,
+    solvent: bool,
+    // set when this check paused the links
+    paused: bool,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(link_hash: String)]
+pub struct CheckSolvencyContext<'info> {
+    pub signer: Signer<'info>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds=[
+            b"tb",
+            link_hash.as_bytes(),
+        ],
+        bump,
+    )]
+    pub token_bridge: Box<Account<'info, TokenBridge>>,
+
+    /// CHECK: the reverse link's PDA, read and written in the handler only when initialized
+    #[account(
+        mut,
+        seeds = [b"tb", token_bridge.reverse_link_hash().as_bytes()],
+        bump,
+    )]
+    pub reverse_token_bridge: UncheckedAccount<'info>,
+
+    #[account(
+        token::mint = mint,
+        token::authority = token_vault,
+        seeds = [b"vault", token_bridge.vault_link_hash().as_bytes()],
+        bump,
+    )]
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
+// Permissionless. Compares the vault balance with what both links sharing it
+// have recorded, and pauses both links on a shortfall if asked to.
+// Token-2022 transfer fee dust can leave the vault above the ledger, never below.
+pub fn check_solvency_handler(
+    ctx: Context<CheckSolvencyContext>,
+    _link_hash: String,
+    pause_if_insolvent: bool,
+) -> Result<()> {
+    let token_bridge = &ctx.accounts.token_bridge;
+    require!(token_bridge.mode == TokenBridgeMode::LockRelease, ErrorCode::NoVaultForLink);
+    require!(token_bridge.solana_mint_addr().is_some(), ErrorCode::NoVaultForLink);
+    token_bridge.check_solana_mint(&ctx.accounts.mint.key())?;
+
+    let reverse_info = ctx.accounts.reverse_token_bridge.to_account_info();
+    let mut reverse_token_bridge = if reverse_info.owner == &crate::ID && !reverse_info.data_is_empty() {
+        Some(TokenBridge::try_deserialize(&mut &reverse_info.try_borrow_data()?[..])?)
+    } else {
+        None
+    };
+
+    let mut expected_balance = token_bridge.ledger_balance();
+    if let Some(reverse_token_bridge) = &reverse_token_bridge {
+        expected_balance += reverse_token_bridge.ledger_balance();
+    }
+    let vault_balance = ctx.accounts.token_vault.amount;
+    let solvent = vault_balance as i128 >= expected_balance;
+
+    let paused = !solvent && pause_if_insolvent;
+    if paused {
+        ctx.accounts.token_bridge.paused = true;
+        if let Some(reverse_token_bridge) = reverse_token_bridge.as_mut() {
+            reverse_token_bridge.paused = true;
+            reverse_token_bridge.try_serialize(&mut &mut reverse_info.try_borrow_mut_data()?[..])?;
+        }
+    }
+
+    emit_cpi!(SolvencyCheckEvent {
+        version: EVENT_VERSION,
+        token_bridge: ctx.accounts.token_bridge.key(),
+        reverse_token_bridge: reverse_token_bridge.map(|_| reverse_info.key()),
+        mint: ctx.accounts.mint.key(),
+        vault_balance,
+        expected_balance,
+        solvent,
+        paused,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked};
+use crate::{error::ErrorCode, state::{BridgeState, TokenBridge, EVENT_VERSION}};
+
+#[event]
+pub struct ProtocolFeesClaimedEvent {
+    version: u8,
+    token_bridge: Pubkey,
+    mint: Pubkey,
+    destination: Pubkey,
+    amount: u64,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(link_hash: String)]
+pub struct ClaimProtocolFeesContext<'info> {
+    pub admin: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"bridge_state"],
+        bump,
+        has_one = admin @ ErrorCode::Unauthorized,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+
+    #[account(
+        mut,
+        seeds=[
+            b"tb",
+            link_hash.as_bytes(),
+        ],
+        bump,
+    )]
+    pub token_bridge: Account<'info, TokenBridge>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = fee_vault,
+        seeds = [b"fee_vault", mint.key().as_ref()],
+        bump,
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn claim_protocol_fees_handler(
+    ctx: Context<ClaimProtocolFeesContext>,
+    _link_hash: String,
+) -> Result<()> {
+    // fees are accrued per link but held in the fee vault of the link's solana mint
+    ctx.accounts.token_bridge.check_solana_mint(&ctx.accounts.mint.key())?;
+    let amount = ctx.accounts.token_bridge.protocol_fees_accrued;
+    require!(amount > 0, ErrorCode::InvalidArgs);
+    ctx.accounts.token_bridge.protocol_fees_accrued = 0;
+
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[
+        &[
+            b"fee_vault",
+            mint_key.as_ref(),
+            &[ctx.bumps.fee_vault]
+        ],
+    ];
+
+    let transfer_checked_t = TransferChecked {
+        authority: ctx.accounts.fee_vault.to_account_info(),
+        from: ctx.accounts.fee_vault.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        to: ctx.accounts.destination.to_account_info(),
+    };
+
+    transfer_checked(
+        CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), transfer_checked_t, signer_seeds),
+        amount,
+        ctx.accounts.mint.decimals
+    )?;
+
+    emit_cpi!(ProtocolFeesClaimedEvent {
+        version: EVENT_VERSION,
+        token_bridge: ctx.accounts.token_bridge.key(),
+        mint: mint_key,
+        destination: ctx.accounts.destination.key(),
+        amount,
+    });
+
+    Ok(())
+}
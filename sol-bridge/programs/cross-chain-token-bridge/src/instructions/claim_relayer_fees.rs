@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked};
+use crate::{error::ErrorCode, state::{RelayerBalance, EVENT_VERSION}};
+
+#[event]
+pub struct RelayerFeesClaimedEvent {
+    version: u8,
+    relayer: Pubkey,
+    mint: Pubkey,
+    destination: Pubkey,
+    amount: u64,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimRelayerFeesContext<'info> {
+    pub relayer: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"relayer_balance", relayer.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub relayer_balance: Account<'info, RelayerBalance>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = fee_vault,
+        seeds = [b"fee_vault", mint.key().as_ref()],
+        bump,
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn claim_relayer_fees_handler(ctx: Context<ClaimRelayerFeesContext>) -> Result<()> {
+    let amount = ctx.accounts.relayer_balance.amount;
+    require!(amount > 0, ErrorCode::InvalidArgs);
+    ctx.accounts.relayer_balance.amount = 0;
+
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[
+        &[
+            b"fee_vault",
+            mint_key.as_ref(),
+            &[ctx.bumps.fee_vault]
+        ],
+    ];
+
+    let transfer_checked_t = TransferChecked {
+        authority: ctx.accounts.fee_vault.to_account_info(),
+        from: ctx.accounts.fee_vault.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        to: ctx.accounts.destination.to_account_info(),
+    };
+
+    transfer_checked(
+        CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), transfer_checked_t, signer_seeds),
+        amount,
+        ctx.accounts.mint.decimals
+    )?;
+
+    emit_cpi!(RelayerFeesClaimedEvent {
+        version: EVENT_VERSION,
+        relayer: ctx.accounts.relayer.key(),
+        mint: mint_key,
+        destination: ctx.accounts.destination.key(),
+        amount,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+use crate::{error::ErrorCode, state::{WithdrawalProof, EVENT_VERSION, WITHDRAWAL_PROOF_EXPIRY}};
+
+#[event]
+pub struct WithdrawalProofClosedEvent {
+    version: u8,
+    withdrawal_proof: Pubkey,
+    nullifier: [u8; 32],
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
+pub struct CloseWithdrawalProofContext<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"withdrawal_proof", creator.key().as_ref(), nullifier.as_ref()],
+        bump,
+        has_one = creator @ ErrorCode::Unauthorized,
+        close = creator,
+    )]
+    pub withdrawal_proof: Account<'info, WithdrawalProof>,
+}
+
+// Reclaims the rent of a proof that was never used by withdraw.
+pub fn close_withdrawal_proof_handler(
+    ctx: Context<CloseWithdrawalProofContext>,
+    nullifier: [u8; 32],
+) -> Result<()> {
+    let expires_at = ctx.accounts.withdrawal_proof.created_at
+        .checked_add(WITHDRAWAL_PROOF_EXPIRY)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(Clock::get()?.unix_timestamp >= expires_at, ErrorCode::WithdrawalProofNotExpired);
+
+    emit_cpi!(WithdrawalProofClosedEvent {
+        version: EVENT_VERSION,
+        withdrawal_proof: ctx.accounts.withdrawal_proof.key(),
+        nullifier,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenInterface};
+use crate::{error::ErrorCode, state::{BridgeState, EVENT_VERSION}};
+
+#[event]
+pub struct WrappedMintCreatedEvent {
+    version: u8,
+    mint: Pubkey,
+    decimals: u8,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(decimals: u8)]
+pub struct CreateWrappedMintContext<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"bridge_state"],
+        bump,
+        has_one = admin @ ErrorCode::Unauthorized,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+
+    /// CHECK: signs mint_to for every MintBurn link, holds no data
+    #[account(
+        seeds = [b"mint_authority"],
+        bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        mint::decimals = decimals,
+        mint::authority = mint_authority,
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+// Wrapped representation of a token native to another chain. Links created
+// with TokenBridgeMode::MintBurn for this mint need no vault liquidity.
+pub fn create_wrapped_mint_handler(ctx: Context<CreateWrappedMintContext>, decimals: u8) -> Result<()> {
+    emit_cpi!(WrappedMintCreatedEvent {
+        version: EVENT_VERSION,
+        mint: ctx.accounts.mint.key(),
+        decimals,
+    });
+
+    Ok(())
+}
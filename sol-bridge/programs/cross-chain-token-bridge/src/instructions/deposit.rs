@@ -1,15 +1,33 @@
-use std::str::FromStr;
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token_interface::{transfer_checked, Mint, TokenAccount, TransferChecked, TokenInterface}};
-use light_sdk::{account::LightAccount, address::v1::derive_address, cpi::{CpiAccounts, CpiInputs}, instruction::merkle_context::PackedAddressMerkleContext, light_compressed_account::pubkey::PubkeyTrait, NewAddressParams, NewAddressParamsPacked, ValidityProof};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{transfer_checked, burn, Burn, Mint, TokenAccount, TransferChecked, TokenInterface}};
+use light_sdk::{account::LightAccount, address::v1::derive_address, cpi::{CpiAccounts, CpiInputs}, instruction::merkle_context::PackedAddressMerkleContext, NewAddressParamsPacked, ValidityProof};
 
-use crate::{error::ErrorCode, state::{BridgeState, DepositRecordCompressedAccount, TokenBridge, SOURCE_CHAIN_ID}, CounterCompressedAccount};
+use crate::{error::ErrorCode, state::{BridgeState, ChainConfig, DepositNonce, DestinationAddress, DepositRecordCompressedAccount, DepositStatus, LiquidityPool, TokenBridge, TokenBridgeMode, EVENT_VERSION, SOURCE_CHAIN_ID}};
 
+// Carries every field of the deposit record, so indexers don't need to read it.
 #[event]
 pub struct DepositEvent {
+    version: u8,
     address: [u8;32],
+    token_bridge: Pubkey,
+    owner: Pubkey,
+    source_chain_id: u32,
+    dest_chain_id: u32,
+    dest_chain_addr: [u8; 32],
+    dest_chain_mint_addr: String,
+    mint: Pubkey,
+    // in the canonical bridge unit, after the fee
+    amount: u64,
+    // in the mint's unit
+    fee: u64,
+    timestamp: i64,
+    deposit_id: u128,
+    status: DepositStatus,
+    // below the canonical unit, left in the depositor's account
+    dust: u64,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(
     proof: ValidityProof,
@@ -17,38 +35,81 @@ pub struct DepositEvent {
     output_merkle_tree_index: u8,
     amount: u64,
     link_hash: String,
-    dest_chain_addr: String,
+    dest_chain_addr: DestinationAddress,
 )]
 pub struct DepositContext<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
+    #[account(mut)]
     pub mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-        mut,
         seeds = [b"bridge_state"],
         bump
     )]
     pub bridge_state: Box<Account<'info, BridgeState>>,
 
     #[account(
+        mut,
         seeds=[
             b"tb",
-            link_hash.as_bytes().as_ref(),
+            link_hash.as_bytes(),
         ],
         bump,
     )]
     pub token_bridge: Box<Account<'info, TokenBridge>>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + DepositNonce::INIT_SPACE,
+        seeds = [b"deposit_nonce", signer.key().as_ref()],
+        bump,
+    )]
+    pub deposit_nonce: Box<Account<'info, DepositNonce>>,
+
+    #[account(
+        seeds = [b"chain_config", token_bridge.dest_chain.to_le_bytes().as_ref()],
+        bump,
+        constraint = chain_config.enabled @ ErrorCode::ChainNotEnabled,
+    )]
+    pub chain_config: Box<Account<'info, ChainConfig>>,
     
+    /// CHECK: seeds only, see TokenBridgeMode::MintBurn
+    #[account(
+        seeds = [b"mint_authority"],
+        bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
     #[account(
         init_if_needed,
         payer=signer,
         token::mint = mint,
         token::authority = token_vault,
-        seeds = [b"vault", mint.key().as_ref()],
+        seeds = [b"vault", token_bridge.vault_link_hash().as_bytes()],
         bump,
     )]
-    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + LiquidityPool::INIT_SPACE,
+        seeds = [b"pool", token_bridge.vault_link_hash().as_bytes()],
+        bump,
+    )]
+    pub liquidity_pool: Option<Box<Account<'info, LiquidityPool>>>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        token::mint = mint,
+        token::authority = fee_vault,
+        seeds = [b"fee_vault", mint.key().as_ref()],
+        bump,
+    )]
+    pub fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
@@ -69,25 +130,120 @@ pub fn deposit_handler<'info> (
     address_merkle_context: PackedAddressMerkleContext,
     output_merkle_tree_index: u8,
     amount: u64,
-    link_hash: String,
-    dest_chain_addr: String,
+    _link_hash: String,
+    dest_chain_addr: DestinationAddress,
 ) -> Result<()> {
 
     require!(amount >0, ErrorCode::DepositAmountShouldBeGreaterThanZero);
-    let tranfer_checked_t = TransferChecked {
-        authority: ctx.accounts.signer.to_account_info(),
-        from: ctx.accounts.user_ata.to_account_info(),
-        mint: ctx.accounts.mint.to_account_info(),
-        to: ctx.accounts.token_vault.to_account_info(),
+    require!(!ctx.accounts.bridge_state.paused, ErrorCode::BridgePaused);
+    require!(!ctx.accounts.token_bridge.paused, ErrorCode::TokenBridgePaused);
+    require!(ctx.accounts.token_bridge.source_chain == SOURCE_CHAIN_ID, ErrorCode::InvalidArgs);
+    ctx.accounts.token_bridge.check_solana_mint(&ctx.accounts.mint.key())?;
+    require!(ctx.accounts.mint.decimals == ctx.accounts.token_bridge.source_decimals, ErrorCode::MintDecimalsMismatch);
+    // a recipient the destination chain can't pay out to would strand the tokens
+    dest_chain_addr.validate(ctx.accounts.chain_config.address_format)?;
+
+    let fee = ctx.accounts.token_bridge.fee_for(amount)?;
+    // only whole canonical units leave the account, the dust is never pulled
+    let (_, dust) = ctx.accounts.token_bridge.normalise_amount((amount - fee) as u128, ctx.accounts.mint.decimals)?;
+    // below one scale factor, which fits in amount
+    let dust = dust as u64;
+    let net_amount = amount - fee - dust;
+    ctx.accounts.token_bridge.record_deposit(Clock::get()?.unix_timestamp, amount - dust)?;
+    let received = match ctx.accounts.token_bridge.mode {
+        TokenBridgeMode::LockRelease => {
+            let token_vault = ctx.accounts.token_vault.as_mut().ok_or(ErrorCode::TokenVaultRequired)?;
+            let balance_before = token_vault.amount;
+            let tranfer_checked_t = TransferChecked {
+                authority: ctx.accounts.signer.to_account_info(),
+                from: ctx.accounts.user_ata.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: token_vault.to_account_info(),
+            };
+
+            transfer_checked(
+                CpiContext::new(ctx.accounts.token_program.to_account_info(), tranfer_checked_t),
+                net_amount,
+                ctx.accounts.mint.decimals,
+            )?;
+
+            // a token-2022 transfer fee is withheld in the vault, only the delta is credited
+            token_vault.reload()?;
+            token_vault.amount.checked_sub(balance_before).ok_or(ErrorCode::MathOverflow)?
+        }
+        TokenBridgeMode::MintBurn => {
+            require!(
+                ctx.accounts.mint.mint_authority == Some(ctx.accounts.mint_authority.key()).into(),
+                ErrorCode::MintNotOwnedByBridge
+            );
+            // the destination chain mints the native token back, nothing is held here
+            let burn_t = Burn {
+                authority: ctx.accounts.signer.to_account_info(),
+                from: ctx.accounts.user_ata.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+            };
+            burn(CpiContext::new(ctx.accounts.token_program.to_account_info(), burn_t), net_amount)?;
+            net_amount
+        }
     };
+    if ctx.accounts.token_bridge.mode == TokenBridgeMode::LockRelease {
+        ctx.accounts.token_bridge.add_locked(received)?;
+        let vault_link_hash = ctx.accounts.token_bridge.vault_link_hash();
+        let liquidity_pool = ctx.accounts.liquidity_pool.as_mut().ok_or(ErrorCode::TokenVaultRequired)?;
+        liquidity_pool.mint = ctx.accounts.mint.key();
+        liquidity_pool.link_hash = vault_link_hash;
+        liquidity_pool.reserve(received)?;
+    }
+    // what a transfer fee leaves below a whole canonical unit stays in the vault
+    let (canonical_amount, _) = ctx.accounts.token_bridge.normalise_amount(received as u128, ctx.accounts.mint.decimals)?;
+    require!(canonical_amount > 0, ErrorCode::DepositAmountShouldBeGreaterThanZero);
 
-    let transfer_checked_cpi = CpiContext::new(ctx.accounts.token_program.to_account_info(), tranfer_checked_t);
+    // the LP share of the fee goes to the vault and accrues to the pool
+    let lp_fee = ctx.accounts.token_bridge.lp_share_of(fee);
+    if lp_fee > 0 {
+        let token_vault = ctx.accounts.token_vault.as_mut().ok_or(ErrorCode::TokenVaultRequired)?;
+        let balance_before = token_vault.amount;
+        let lp_fee_transfer_t = TransferChecked {
+            authority: ctx.accounts.signer.to_account_info(),
+            from: ctx.accounts.user_ata.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: token_vault.to_account_info(),
+        };
+        transfer_checked(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), lp_fee_transfer_t),
+            lp_fee,
+            ctx.accounts.mint.decimals,
+        )?;
+        token_vault.reload()?;
+        let lp_fee_received = token_vault.amount.checked_sub(balance_before).ok_or(ErrorCode::MathOverflow)?;
+        ctx.accounts.liquidity_pool.as_mut().ok_or(ErrorCode::TokenVaultRequired)?.add_liquidity(lp_fee_received)?;
+        let token_bridge = &mut ctx.accounts.token_bridge;
+        token_bridge.total_liquidity_provided = token_bridge.total_liquidity_provided
+            .checked_add(lp_fee_received)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
 
-    let _res = transfer_checked(
-        transfer_checked_cpi,
-        amount,
-        ctx.accounts.mint.decimals
-    )?;
+    let protocol_fee = fee - lp_fee;
+    if protocol_fee > 0 {
+        let fee_balance_before = ctx.accounts.fee_vault.amount;
+        let fee_transfer_t = TransferChecked {
+            authority: ctx.accounts.signer.to_account_info(),
+            from: ctx.accounts.user_ata.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.fee_vault.to_account_info(),
+        };
+        transfer_checked(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), fee_transfer_t),
+            protocol_fee,
+            ctx.accounts.mint.decimals,
+        )?;
+        ctx.accounts.fee_vault.reload()?;
+        let fee_received = ctx.accounts.fee_vault.amount.checked_sub(fee_balance_before).ok_or(ErrorCode::MathOverflow)?;
+        let token_bridge = &mut ctx.accounts.token_bridge;
+        token_bridge.protocol_fees_accrued = token_bridge.protocol_fees_accrued
+            .checked_add(fee_received)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
 
     let program_id = crate::ID.into();
     let light_cpi_accounts = CpiAccounts::new(
@@ -96,17 +252,21 @@ pub fn deposit_handler<'info> (
         crate::ID,
     ).map_err(ProgramError::from)?;
     
-    let current_deposit_num = ctx.accounts
-        .bridge_state
-        .deposit_count
-        .checked_add(1)
-        .unwrap();
+    // per user sequence, deposits by other users don't contend
+    let deposit_nonce = &mut ctx.accounts.deposit_nonce;
+    let current_deposit_num = deposit_nonce.nonce.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+    deposit_nonce.owner = ctx.accounts.signer.key();
+    deposit_nonce.nonce = current_deposit_num;
 
-    // Persist the updated counter so that subsequent deposits use the correct value
-    ctx.accounts.bridge_state.deposit_count = current_deposit_num;
+    let token_bridge = &mut ctx.accounts.token_bridge;
+    token_bridge.deposit_count = token_bridge.deposit_count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
 
     let (address, address_seed) = derive_address(
-        &[b"deposit", ctx.accounts.signer.key().as_ref(), current_deposit_num.to_le_bytes().as_ref()],
+        &[
+            b"deposit",
+            ctx.accounts.signer.key().as_ref(),
+            current_deposit_num.to_le_bytes().as_ref(),
+        ],
         &light_cpi_accounts.tree_accounts()[address_merkle_context.address_merkle_tree_pubkey_index as usize].key(),
         &crate::ID,
     );
@@ -127,15 +287,17 @@ pub fn deposit_handler<'info> (
     
     deposit_record.owner = ctx.accounts.signer.key();
     deposit_record.mint = ctx.accounts.mint.key();
-    msg!("amount {:?}", amount);
-    deposit_record.amount = amount;
+    deposit_record.amount = canonical_amount;
+    deposit_record.fee = fee;
     deposit_record.source_chain_id = token_bridge.source_chain;
     deposit_record.dest_chain_id = token_bridge.dest_chain;
-    deposit_record.dest_chain_addr = dest_chain_addr;
+    deposit_record.dest_chain_addr = dest_chain_addr.to_record_bytes();
     deposit_record.dest_chain_mint_addr = token_bridge.dest_chain_mint_addr.clone();
     deposit_record.timestamp = Clock::get()?.unix_timestamp;
-    deposit_record.deposit_id = current_deposit_num;
+    deposit_record.deposit_id = current_deposit_num as u128;
+    deposit_record.status = DepositStatus::Pending;
     
+    let event_record = (*deposit_record).clone();
     let cpi = CpiInputs::new_with_address(
         proof,
         vec![deposit_record.to_account_info().map_err(|e| {
@@ -151,7 +313,23 @@ pub fn deposit_handler<'info> (
         ProgramError::from(e)
     })?;
 
-    emit!(DepositEvent{address});
+    emit_cpi!(DepositEvent{
+        version: EVENT_VERSION,
+        address,
+        token_bridge: ctx.accounts.token_bridge.key(),
+        owner: event_record.owner,
+        source_chain_id: event_record.source_chain_id,
+        dest_chain_id: event_record.dest_chain_id,
+        dest_chain_addr: event_record.dest_chain_addr,
+        dest_chain_mint_addr: event_record.dest_chain_mint_addr,
+        mint: event_record.mint,
+        amount: event_record.amount,
+        fee: event_record.fee,
+        timestamp: event_record.timestamp,
+        deposit_id: event_record.deposit_id,
+        status: event_record.status,
+        dust,
+    });
 
     Ok(())
 }
\ No newline at end of file
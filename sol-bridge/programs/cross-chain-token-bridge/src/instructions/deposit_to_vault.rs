@@ -1,40 +1,107 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{transfer_checked, mint_to, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked}};
 
+use crate::{error::ErrorCode, state::{BridgeState, LiquidityPool, TokenBridge, TokenBridgeMode, EVENT_VERSION}};
+
+#[event]
+pub struct LiquidityDepositedEvent {
+    version: u8,
+    token_bridge: Pubkey,
+    provider: Pubkey,
+    mint: Pubkey,
+    // credited to the pool, after any token-2022 transfer fee
+    amount: u64,
+    shares: u64,
+}
+
+#[event_cpi]
 #[derive(Accounts)]
+#[instruction(link_hash: String)]
 pub struct DepositToVaultContext<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
-    pub mint: InterfaceAccount<'info, Mint>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"bridge_state"],
+        bump
+    )]
+    pub bridge_state: Box<Account<'info, BridgeState>>,
+
+    #[account(
+        mut,
+        seeds=[
+            b"tb",
+            link_hash.as_bytes(),
+        ],
+        bump,
+    )]
+    pub token_bridge: Box<Account<'info, TokenBridge>>,
 
     #[account(
         init_if_needed,
         payer = signer,
         token::mint = mint,
         token::authority = token_vault,
-        seeds = [b"vault", mint.key().as_ref()],
+        seeds = [b"vault", token_bridge.vault_link_hash().as_bytes()],
         bump
     )]
-    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + LiquidityPool::INIT_SPACE,
+        seeds = [b"pool", token_bridge.vault_link_hash().as_bytes()],
+        bump,
+    )]
+    pub liquidity_pool: Box<Account<'info, LiquidityPool>>,
+
+    // pool share token, priced against liquidity_pool.total_liquidity
+    #[account(
+        init_if_needed,
+        payer = signer,
+        mint::decimals = mint.decimals,
+        mint::authority = lp_mint,
+        mint::token_program = token_program,
+        seeds = [b"lp_mint", token_bridge.vault_link_hash().as_bytes()],
+        bump,
+    )]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = lp_mint,
+        associated_token::authority = signer,
+    )]
+    pub user_lp_ata: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
         associated_token::authority = signer,
         associated_token::mint = mint
     )]
-    pub user_ata: InterfaceAccount<'info, TokenAccount>,
+    pub user_ata: Box<InterfaceAccount<'info, TokenAccount>>,
 
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn deposit_to_vault_handler(
-    ctx: Context<DepositToVaultContext>,
+// Adds payout liquidity to the vault in exchange for pool shares.
+pub fn deposit_to_vault_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, DepositToVaultContext<'info>>,
+    _link_hash: String,
     amount: u64
 ) -> Result<()> {
+    require!(!ctx.accounts.bridge_state.paused, ErrorCode::BridgePaused);
+    require!(!ctx.accounts.token_bridge.paused, ErrorCode::TokenBridgePaused);
+    require!(ctx.accounts.token_bridge.mode == TokenBridgeMode::LockRelease, ErrorCode::InvalidArgs);
+    ctx.accounts.token_bridge.check_solana_mint(&ctx.accounts.mint.key())?;
 
+    let balance_before = ctx.accounts.token_vault.amount;
     let transfer_checked_t = TransferChecked {
         authority: ctx.accounts.signer.to_account_info(),
         from: ctx.accounts.user_ata.to_account_info(),
@@ -42,16 +109,55 @@ pub fn deposit_to_vault_handler(
         to: ctx.accounts.token_vault.to_account_info(),
     };
 
-    let transfer_checked_cpi = CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        transfer_checked_t,
-    );
-
-    let _res = transfer_checked(
-        transfer_checked_cpi,
+    transfer_checked(
+        CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_checked_t),
         amount,
-        ctx.accounts.mint.decimals
+        ctx.accounts.mint.decimals,
     )?;
 
+    ctx.accounts.token_vault.reload()?;
+    let received = ctx.accounts.token_vault.amount.checked_sub(balance_before).ok_or(ErrorCode::MathOverflow)?;
+
+    let token_bridge = &mut ctx.accounts.token_bridge;
+    token_bridge.total_liquidity_provided = token_bridge.total_liquidity_provided
+        .checked_add(received)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let liquidity_pool = &mut ctx.accounts.liquidity_pool;
+    liquidity_pool.mint = ctx.accounts.mint.key();
+    liquidity_pool.link_hash = token_bridge.vault_link_hash();
+    let shares = liquidity_pool.shares_for(received, ctx.accounts.lp_mint.supply)?;
+    require!(shares > 0, ErrorCode::InvalidArgs);
+    liquidity_pool.add_liquidity(received)?;
+
+    let mint_key = ctx.accounts.mint.key();
+    let vault_link_hash = token_bridge.vault_link_hash();
+    let signer_seeds: &[&[&[u8]]] = &[
+        &[
+            b"lp_mint",
+            vault_link_hash.as_bytes(),
+            &[ctx.bumps.lp_mint]
+        ],
+    ];
+
+    let mint_to_t = MintTo {
+        authority: ctx.accounts.lp_mint.to_account_info(),
+        mint: ctx.accounts.lp_mint.to_account_info(),
+        to: ctx.accounts.user_lp_ata.to_account_info(),
+    };
+    mint_to(
+        CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), mint_to_t, signer_seeds),
+        shares,
+    )?;
+
+    emit_cpi!(LiquidityDepositedEvent {
+        version: EVENT_VERSION,
+        token_bridge: ctx.accounts.token_bridge.key(),
+        provider: ctx.accounts.signer.key(),
+        mint: mint_key,
+        amount: received,
+        shares,
+    });
+
     Ok(())
-}
\ No newline at end of file
+}
@@ -1,13 +1,25 @@
 use anchor_lang::prelude::*;
-use crate::state::BridgeState;
+// deprecated in favour of solana-loader-v3-interface, which isn't a dependency
+#[allow(deprecated)]
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use crate::{error::ErrorCode, state::{BridgeState, EVENT_VERSION}};
 
+#[event]
+pub struct BridgeInitializedEvent {
+    version: u8,
+    admin: Pubkey,
+    nullifier_root: [u8; 32],
+}
+
+#[event_cpi]
 #[derive(Accounts)]
 pub struct InitContext<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    // plain `init`: once created, the admin can only change through propose/accept
     #[account(
-        init_if_needed,
+        init,
         space = 8 + BridgeState::INIT_SPACE,
         payer = signer,
         seeds=[b"bridge_state"],
@@ -15,11 +27,35 @@ pub struct InitContext<'info> {
     )]
     pub bridge_state: Account<'info, BridgeState>,
 
+    // only the upgrade authority may claim the bridge, so nobody can front-run
+    // `init` between deployment and the first call
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(signer.key()) @ ErrorCode::Unauthorized,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
     pub system_program: Program<'info, System>,
 }
 
 
-pub fn init_handler(ctx: Context<InitContext>) -> Result<()> {
-    ctx.accounts.bridge_state.deposit_count = 0;
+pub fn init_handler(ctx: Context<InitContext>, nullifier_root: [u8; 32]) -> Result<()> {
+    let bridge_state = &mut ctx.accounts.bridge_state;
+    bridge_state.admin = ctx.accounts.signer.key();
+    bridge_state.pending_admin = None;
+    bridge_state.paused = false;
+    // the admin reports deposit status until set_relayer names a relayer
+    bridge_state.relayer = ctx.accounts.signer.key();
+    // root of the relayer's empty nullifier tree
+    bridge_state.nullifier_root = nullifier_root;
+
+    emit_cpi!(BridgeInitializedEvent {
+        version: EVENT_VERSION,
+        admin: ctx.accounts.signer.key(),
+        nullifier_root,
+    });
+
     Ok(())
-}
\ No newline at end of file
+}
@@ -1,48 +1,150 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::hash::hash;
-use crate::state::TokenBridge;
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+        state::Mint as MintState,
+    },
+    token_interface::Mint,
+};
+use crate::{error::ErrorCode, state::{derive_link_hash, BridgeState, ChainConfig, TokenBridge, TokenBridgeMode, EVENT_VERSION, SOURCE_CHAIN_ID}};
 
+#[event]
+pub struct TokenBridgeCreatedEvent {
+    version: u8,
+    token_bridge: Pubkey,
+    link_hash: String,
+    source_chain: u32,
+    source_chain_mint_addr: String,
+    dest_chain: u32,
+    dest_chain_mint_addr: String,
+    source_decimals: u8,
+    dest_decimals: u8,
+    mode: TokenBridgeMode,
+}
+
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(
     source_chain: u32,
     source_chain_mint_addr: String,
     dest_chain: u32,
     dest_chain_mint_addr: String,
-    link_hash: String,
 )]
 pub struct InitTokenBridgeContext<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
     #[account(
-        init_if_needed,
+        seeds = [b"bridge_state"],
+        bump,
+        constraint = bridge_state.admin == signer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+
+    #[account(
+        seeds = [b"chain_config", dest_chain.to_le_bytes().as_ref()],
+        bump,
+        constraint = chain_config.enabled @ ErrorCode::ChainNotEnabled,
+    )]
+    pub chain_config: Account<'info, ChainConfig>,
+
+    #[account(
+        init,
         payer=signer,
         space=8+TokenBridge::INIT_SPACE,
         seeds=[
             b"tb",
-            link_hash.as_bytes().as_ref(),
+            derive_link_hash(source_chain, &source_chain_mint_addr, dest_chain, &dest_chain_mint_addr).as_bytes(),
         ],
         bump,
     )]
     pub token_bridge: Account<'info, TokenBridge>,
 
+    // the solana side's mint, required when either end of the link is solana
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
     pub system_program: Program<'info, System>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn init_token_bridge_handler(
     ctx: Context<InitTokenBridgeContext>,
     source_chain: u32,
     source_chain_mint_addr: String,
     dest_chain: u32,
     dest_chain_mint_addr: String,
-    link_hash: String,
+    mode: TokenBridgeMode,
+    source_decimals: u8,
+    dest_decimals: u8,
 ) -> Result<()> {
+    // the mint address and the decimals declared for the solana end
+    let solana_side = if source_chain == SOURCE_CHAIN_ID {
+        Some((&source_chain_mint_addr, source_decimals))
+    } else if dest_chain == SOURCE_CHAIN_ID {
+        Some((&dest_chain_mint_addr, dest_decimals))
+    } else {
+        None
+    };
+    if let Some((solana_mint_addr, solana_decimals)) = solana_side {
+        let mint = ctx.accounts.mint.as_ref().ok_or(ErrorCode::InvalidArgs)?;
+        require!(mint.key().to_string() == *solana_mint_addr, ErrorCode::InvalidArgs);
+        // amounts are scaled by these decimals, a wrong value would mis-price every transfer
+        require!(mint.decimals == solana_decimals, ErrorCode::MintDecimalsMismatch);
+        check_mint_extensions(&mint.to_account_info())?;
+    }
+
     let token_bridge = &mut ctx.accounts.token_bridge;
+    token_bridge.link_hash = derive_link_hash(source_chain, &source_chain_mint_addr, dest_chain, &dest_chain_mint_addr);
     token_bridge.source_chain = source_chain;
     token_bridge.source_chain_mint_addr = source_chain_mint_addr;
     token_bridge.dest_chain = dest_chain;
     token_bridge.dest_chain_mint_addr = dest_chain_mint_addr;
-    // token_bridge.link_hash = link_hash;
+    token_bridge.source_decimals = source_decimals;
+    token_bridge.dest_decimals = dest_decimals;
+    token_bridge.mode = mode;
+    token_bridge.paused = false;
+
+    let token_bridge = &ctx.accounts.token_bridge;
 
+    emit_cpi!(TokenBridgeCreatedEvent {
+        version: EVENT_VERSION,
+        token_bridge: token_bridge.key(),
+        link_hash: token_bridge.link_hash.clone(),
+        source_chain: token_bridge.source_chain,
+        source_chain_mint_addr: token_bridge.source_chain_mint_addr.clone(),
+        dest_chain: token_bridge.dest_chain,
+        dest_chain_mint_addr: token_bridge.dest_chain_mint_addr.clone(),
+        source_decimals: token_bridge.source_decimals,
+        dest_decimals: token_bridge.dest_decimals,
+        mode: token_bridge.mode,
+    });
+
+    Ok(())
+}
+
+// Token-2022 extensions a link can carry. Transfer fees are handled by
+// crediting balance deltas; anything that lets a third party move, freeze or
+// hide vault balances, or close and re-create the mint, is refused. So are
+// transfer hooks: the bridge's transfers don't forward a hook's extra accounts.
+const SUPPORTED_MINT_EXTENSIONS: &[ExtensionType] = &[
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+    ExtensionType::GroupPointer,
+    ExtensionType::TokenGroup,
+    ExtensionType::GroupMemberPointer,
+    ExtensionType::TokenGroupMember,
+    ExtensionType::InterestBearingConfig,
+];
+
+fn check_mint_extensions(mint: &AccountInfo) -> Result<()> {
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<MintState>::unpack(&data)?;
+    for extension in mint_state.get_extension_types()? {
+        if !SUPPORTED_MINT_EXTENSIONS.contains(&extension) {
+            msg!("unsupported mint extension {:?}", extension);
+            return err!(ErrorCode::UnsupportedMintExtension);
+        }
+    }
     Ok(())
-}
\ No newline at end of file
+}
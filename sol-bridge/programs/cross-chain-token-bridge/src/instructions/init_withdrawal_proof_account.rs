@@ -1,7 +1,16 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::hash::hash;
-use crate::state::{TokenBridge, WithdrawalProof};
+use crate::state::{WithdrawalProof, EVENT_VERSION};
 
+#[event]
+pub struct WithdrawalProofStoredEvent {
+    version: u8,
+    withdrawal_proof: Pubkey,
+    nullifier: [u8; 32],
+    old_root: [u8; 32],
+    new_root: [u8; 32],
+}
+
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(
     withdrawal_id: u128,
@@ -9,18 +18,21 @@ use crate::state::{TokenBridge, WithdrawalProof};
     proof_b: [u8; 128],
     proof_c: [u8; 64],
     nullifier: [u8; 32],
+    old_root: [u8; 32],
     new_root: [u8; 32],
 )]
 pub struct InitWithdrawalProofAccountContext<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    // written once per nullifier, close_withdrawal_proof frees it for a retry
     #[account(
-        init_if_needed,
+        init,
         payer=signer,
         space=8+WithdrawalProof::INIT_SPACE,
         seeds=[
             b"withdrawal_proof",
+            signer.key().as_ref(),
             nullifier.as_ref(),
         ],
         bump,
@@ -30,21 +42,34 @@ pub struct InitWithdrawalProofAccountContext<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn init_withdrawal_proof_account_handler(
     ctx: Context<InitWithdrawalProofAccountContext>,
-    withdrawal_id: u128,
+    _withdrawal_id: u128,
     proof_a: [u8; 64],
     proof_b: [u8; 128],
     proof_c: [u8; 64],
     nullifier: [u8; 32],
+    old_root: [u8; 32],
     new_root: [u8; 32],
 ) -> Result<()> {
     let withdrawal_proof = &mut ctx.accounts.withdrawal_proof;
+    withdrawal_proof.creator = ctx.accounts.signer.key();
+    withdrawal_proof.created_at = Clock::get()?.unix_timestamp;
     withdrawal_proof.proof_a = proof_a;
     withdrawal_proof.proof_b = proof_b;
     withdrawal_proof.proof_c = proof_c;
     withdrawal_proof.nullifier = nullifier;
+    withdrawal_proof.old_root = old_root;
     withdrawal_proof.new_root = new_root;
 
+    emit_cpi!(WithdrawalProofStoredEvent {
+        version: EVENT_VERSION,
+        withdrawal_proof: ctx.accounts.withdrawal_proof.key(),
+        nullifier,
+        old_root,
+        new_root,
+    });
+
     Ok(())
 }
\ No newline at end of file
@@ -9,4 +9,52 @@ pub use withdraw::*;
 pub mod init_withdrawal_proof_account;
 pub use init_withdrawal_proof_account::*;
 pub mod deposit_to_vault;
-pub use deposit_to_vault::*;
\ No newline at end of file
+pub use deposit_to_vault::*;
+pub mod propose_admin;
+pub use propose_admin::*;
+pub mod accept_admin;
+pub use accept_admin::*;
+pub mod set_bridge_paused;
+pub use set_bridge_paused::*;
+pub mod set_token_bridge_paused;
+pub use set_token_bridge_paused::*;
+pub mod set_token_bridge_limits;
+pub use set_token_bridge_limits::*;
+pub mod register_verifying_key;
+pub use register_verifying_key::*;
+pub mod append_verifying_key_ic;
+pub use append_verifying_key_ic::*;
+pub mod activate_verifying_key;
+pub use activate_verifying_key::*;
+pub mod retire_verifying_key;
+pub use retire_verifying_key::*;
+pub mod set_token_bridge_fees;
+pub use set_token_bridge_fees::*;
+pub mod claim_protocol_fees;
+pub use claim_protocol_fees::*;
+pub mod claim_relayer_fees;
+pub use claim_relayer_fees::*;
+pub mod set_chain_config;
+pub use set_chain_config::*;
+pub mod create_wrapped_mint;
+pub use create_wrapped_mint::*;
+pub mod set_token_bridge_refund_timeout;
+pub use set_token_bridge_refund_timeout::*;
+pub mod refund_deposit;
+pub use refund_deposit::*;
+pub mod set_relayer;
+pub use set_relayer::*;
+pub mod update_deposit_status;
+pub use update_deposit_status::*;
+pub mod reset_relayed_deposit;
+pub use reset_relayed_deposit::*;
+pub mod withdraw_liquidity;
+pub use withdraw_liquidity::*;
+pub mod check_solvency;
+pub use check_solvency::*;
+pub mod sync_bridge_stats;
+pub use sync_bridge_stats::*;
+pub mod close_withdrawal_proof;
+pub use close_withdrawal_proof::*;
+pub mod withdraw_with_proof;
+pub use withdraw_with_proof::*;
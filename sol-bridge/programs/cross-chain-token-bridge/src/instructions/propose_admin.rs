@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+use crate::{error::ErrorCode, state::{BridgeState, EVENT_VERSION}};
+
+#[event]
+pub struct AdminProposedEvent {
+    version: u8,
+    admin: Pubkey,
+    pending_admin: Pubkey,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ProposeAdminContext<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"bridge_state"],
+        bump,
+        has_one = admin @ ErrorCode::Unauthorized,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+}
+
+pub fn propose_admin_handler(
+    ctx: Context<ProposeAdminContext>,
+    new_admin: Pubkey,
+) -> Result<()> {
+    // the current admin stays in control until `new_admin` accepts
+    ctx.accounts.bridge_state.pending_admin = Some(new_admin);
+
+    emit_cpi!(AdminProposedEvent {
+        version: EVENT_VERSION,
+        admin: ctx.accounts.admin.key(),
+        pending_admin: new_admin,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{transfer_checked, mint_to, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked}};
+use light_sdk::{account::LightAccount, cpi::{CpiAccounts, CpiInputs}, instruction::account_meta::CompressedAccountMeta, ValidityProof};
+
+use crate::{error::ErrorCode, state::{BridgeState, DepositRecordCompressedAccount, DepositStatus, LiquidityPool, TokenBridge, TokenBridgeMode, EVENT_VERSION}};
+
+#[event]
+pub struct DepositRefundedEvent {
+    version: u8,
+    address: [u8; 32],
+    owner: Pubkey,
+    mint: Pubkey,
+    // in the mint's unit
+    amount: u64,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(
+    proof: ValidityProof,
+    link_hash: String,
+)]
+pub struct RefundDepositContext<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(mut)]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"bridge_state"],
+        bump
+    )]
+    pub bridge_state: Box<Account<'info, BridgeState>>,
+
+    #[account(
+        mut,
+        seeds=[
+            b"tb",
+            link_hash.as_bytes(),
+        ],
+        bump,
+    )]
+    pub token_bridge: Box<Account<'info, TokenBridge>>,
+
+    /// CHECK: seeds only, see TokenBridgeMode::MintBurn
+    #[account(
+        seeds = [b"mint_authority"],
+        bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = token_vault,
+        seeds = [b"vault", token_bridge.vault_link_hash().as_bytes()],
+        bump,
+    )]
+    pub token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", token_bridge.vault_link_hash().as_bytes()],
+        bump,
+    )]
+    pub liquidity_pool: Option<Box<Account<'info, LiquidityPool>>>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = mint,
+        associated_token::authority = signer,
+    )]
+    pub user_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Returns a deposit the relayer never picked up. The record is marked
+// Refunded rather than closed so the relayer and the destination chain can
+// tell it must not be released. The deposit fee is not refunded.
+pub fn refund_deposit_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, RefundDepositContext<'info>>,
+    proof: ValidityProof,
+    _link_hash: String,
+    deposit_record: DepositRecordCompressedAccount,
+    account_meta: CompressedAccountMeta,
+) -> Result<()> {
+    require!(!ctx.accounts.bridge_state.paused, ErrorCode::BridgePaused);
+    let token_bridge = &ctx.accounts.token_bridge;
+    require!(!token_bridge.paused, ErrorCode::TokenBridgePaused);
+    require!(deposit_record.owner == ctx.accounts.signer.key(), ErrorCode::Unauthorized);
+    require!(deposit_record.mint == ctx.accounts.mint.key(), ErrorCode::InvalidArgs);
+    token_bridge.check_solana_mint(&ctx.accounts.mint.key())?;
+    require!(
+        deposit_record.source_chain_id == token_bridge.source_chain && deposit_record.dest_chain_id == token_bridge.dest_chain,
+        ErrorCode::InvalidArgs
+    );
+    // links to the same chain differ only in the destination mint
+    require!(deposit_record.dest_chain_mint_addr == token_bridge.dest_chain_mint_addr, ErrorCode::InvalidArgs);
+    require!(ctx.accounts.mint.decimals == token_bridge.source_decimals, ErrorCode::MintDecimalsMismatch);
+    require!(deposit_record.status == DepositStatus::Pending, ErrorCode::DepositNotPending);
+    require!(token_bridge.refund_timeout > 0, ErrorCode::RefundsDisabled);
+    let refundable_at = deposit_record.timestamp
+        .checked_add(token_bridge.refund_timeout)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(Clock::get()?.unix_timestamp >= refundable_at, ErrorCode::RefundTimeoutNotReached);
+
+    let refund_amount = token_bridge.denormalise_amount(deposit_record.amount, ctx.accounts.mint.decimals)?;
+
+    // the light system program checks deposit_record against the stored hash
+    let program_id = crate::ID.into();
+    let mut record = LightAccount::<'_, DepositRecordCompressedAccount>::new_mut(
+        &program_id,
+        &account_meta,
+        deposit_record,
+    )
+    .map_err(ProgramError::from)?;
+
+    record.status = DepositStatus::Refunded;
+
+    match token_bridge.mode {
+        TokenBridgeMode::LockRelease => {
+            let token_vault = ctx.accounts.token_vault.as_ref().ok_or(ErrorCode::TokenVaultRequired)?;
+            let vault_link_hash = ctx.accounts.token_bridge.vault_link_hash();
+            let signer_seeds: &[&[&[u8]]] = &[
+                &[
+                    b"vault",
+                    vault_link_hash.as_bytes(),
+                    &[ctx.bumps.token_vault.unwrap()]
+                ],
+            ];
+
+            let transfer_checked_t = TransferChecked {
+                authority: token_vault.to_account_info(),
+                from: token_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.user_ata.to_account_info(),
+            };
+            transfer_checked(
+                CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), transfer_checked_t, signer_seeds),
+                refund_amount,
+                ctx.accounts.mint.decimals,
+            )?;
+        }
+        TokenBridgeMode::MintBurn => {
+            require!(
+                ctx.accounts.mint.mint_authority == Some(ctx.accounts.mint_authority.key()).into(),
+                ErrorCode::MintNotOwnedByBridge
+            );
+            let signer_seeds: &[&[&[u8]]] = &[
+                &[
+                    b"mint_authority",
+                    &[ctx.bumps.mint_authority]
+                ],
+            ];
+
+            let mint_to_t = MintTo {
+                authority: ctx.accounts.mint_authority.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.user_ata.to_account_info(),
+            };
+            mint_to(
+                CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), mint_to_t, signer_seeds),
+                refund_amount,
+            )?;
+        }
+    }
+
+    if ctx.accounts.token_bridge.mode == TokenBridgeMode::LockRelease {
+        ctx.accounts.token_bridge.add_released(refund_amount)?;
+        ctx.accounts.liquidity_pool.as_mut().ok_or(ErrorCode::TokenVaultRequired)?.pay_out(refund_amount)?;
+    }
+
+    let light_cpi_accounts = CpiAccounts::new(
+        ctx.accounts.signer.as_ref(),
+        ctx.remaining_accounts,
+        crate::ID,
+    )
+    .map_err(ProgramError::from)?;
+
+    let cpi = CpiInputs::new(
+        proof,
+        vec![record.to_account_info().map_err(ProgramError::from)?],
+    );
+
+    cpi.invoke_light_system_program(light_cpi_accounts)
+        .map_err(ProgramError::from)?;
+
+    emit_cpi!(DepositRefundedEvent {
+        version: EVENT_VERSION,
+        address: account_meta.address,
+        owner: ctx.accounts.signer.key(),
+        mint: ctx.accounts.mint.key(),
+        amount: refund_amount,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+use crate::{error::ErrorCode, state::{BridgeState, VerifyingKey, VerifyingKeyStatus, EVENT_VERSION, MAX_VK_PUBLIC_INPUTS}};
+
+// also emitted by activate_verifying_key and retire_verifying_key
+#[event]
+pub struct VerifyingKeyStatusEvent {
+    pub version: u8,
+    pub circuit_id: u32,
+    pub vk_version: u32,
+    pub status: VerifyingKeyStatus,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(circuit_id: u32, version: u32)]
+pub struct RegisterVerifyingKeyContext<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"bridge_state"],
+        bump,
+        has_one = admin @ ErrorCode::Unauthorized,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + VerifyingKey::INIT_SPACE,
+        seeds = [b"vk", circuit_id.to_le_bytes().as_ref(), version.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub verifying_key: Box<Account<'info, VerifyingKey>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// vk_ic can be partial: keys with many public inputs don't fit in one
+// transaction and are completed with append_verifying_key_ic.
+#[allow(clippy::too_many_arguments)]
+pub fn register_verifying_key_handler(
+    ctx: Context<RegisterVerifyingKeyContext>,
+    circuit_id: u32,
+    version: u32,
+    nr_public_inputs: u8,
+    vk_alpha_g1: [u8; 64],
+    vk_beta_g2: [u8; 128],
+    vk_gamma_g2: [u8; 128],
+    vk_delta_g2: [u8; 128],
+    vk_ic: Vec<[u8; 64]>,
+) -> Result<()> {
+    require!(nr_public_inputs as usize <= MAX_VK_PUBLIC_INPUTS, ErrorCode::InvalidArgs);
+    require!(vk_ic.len() <= nr_public_inputs as usize + 1, ErrorCode::InvalidArgs);
+    // delta == gamma means no phase-2 contribution, and a point at infinity
+    // drops its term from the pairing check; proofs against either can be forged
+    require!(vk_gamma_g2 != vk_delta_g2, ErrorCode::InvalidVerifyingKey);
+    require!(vk_alpha_g1 != [0u8; 64], ErrorCode::InvalidVerifyingKey);
+    require!(vk_ic.iter().all(|point| *point != [0u8; 64]), ErrorCode::InvalidVerifyingKey);
+
+    let verifying_key = &mut ctx.accounts.verifying_key;
+    verifying_key.circuit_id = circuit_id;
+    verifying_key.version = version;
+    verifying_key.status = VerifyingKeyStatus::Registered;
+    verifying_key.nr_public_inputs = nr_public_inputs;
+    verifying_key.vk_alpha_g1 = vk_alpha_g1;
+    verifying_key.vk_beta_g2 = vk_beta_g2;
+    verifying_key.vk_gamma_g2 = vk_gamma_g2;
+    verifying_key.vk_delta_g2 = vk_delta_g2;
+    verifying_key.vk_ic = vk_ic;
+
+    emit_cpi!(VerifyingKeyStatusEvent {
+        version: EVENT_VERSION,
+        circuit_id,
+        vk_version: version,
+        status: VerifyingKeyStatus::Registered,
+    });
+
+    Ok(())
+}
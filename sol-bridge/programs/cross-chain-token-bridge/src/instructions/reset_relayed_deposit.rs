@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+use light_sdk::{account::LightAccount, cpi::{CpiAccounts, CpiInputs}, instruction::account_meta::CompressedAccountMeta, ValidityProof};
+
+use crate::{error::ErrorCode, instructions::DepositStatusUpdatedEvent, state::{BridgeState, DepositRecordCompressedAccount, DepositStatus, TokenBridge, EVENT_VERSION}};
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(proof: ValidityProof, link_hash: String)]
+pub struct ResetRelayedDepositContext<'info> {
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    #[account(
+        seeds = [b"bridge_state"],
+        bump,
+        has_one = relayer @ ErrorCode::Unauthorized,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+
+    #[account(
+        seeds=[
+            b"tb",
+            link_hash.as_bytes(),
+        ],
+        bump,
+    )]
+    pub token_bridge: Account<'info, TokenBridge>,
+}
+
+// Puts a Relayed deposit whose release never landed back to Pending so its
+// owner can refund it. The relayer first cancels the deposit on the
+// destination chain, cancel_tx_hash, so the signed release can't land after.
+// Only once the link's refund timeout has passed, like the refund itself.
+pub fn reset_relayed_deposit_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, ResetRelayedDepositContext<'info>>,
+    proof: ValidityProof,
+    _link_hash: String,
+    deposit_record: DepositRecordCompressedAccount,
+    account_meta: CompressedAccountMeta,
+    cancel_tx_hash: [u8; 32],
+) -> Result<()> {
+    let token_bridge = &ctx.accounts.token_bridge;
+    require!(deposit_record.status == DepositStatus::Relayed, ErrorCode::InvalidDepositStatusTransition);
+    require!(
+        deposit_record.source_chain_id == token_bridge.source_chain
+            && deposit_record.dest_chain_id == token_bridge.dest_chain
+            && deposit_record.dest_chain_mint_addr == token_bridge.dest_chain_mint_addr,
+        ErrorCode::InvalidArgs
+    );
+    require!(cancel_tx_hash != [0u8; 32], ErrorCode::InvalidArgs);
+    require!(token_bridge.refund_timeout > 0, ErrorCode::RefundsDisabled);
+    let resettable_at = deposit_record.timestamp
+        .checked_add(token_bridge.refund_timeout)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(Clock::get()?.unix_timestamp >= resettable_at, ErrorCode::RefundTimeoutNotReached);
+
+    let program_id = crate::ID.into();
+    let mut record = LightAccount::<'_, DepositRecordCompressedAccount>::new_mut(
+        &program_id,
+        &account_meta,
+        deposit_record,
+    )
+    .map_err(ProgramError::from)?;
+
+    record.status = DepositStatus::Pending;
+    record.dest_tx_hash = cancel_tx_hash;
+
+    let light_cpi_accounts = CpiAccounts::new(
+        ctx.accounts.relayer.as_ref(),
+        ctx.remaining_accounts,
+        crate::ID,
+    )
+    .map_err(ProgramError::from)?;
+
+    let cpi = CpiInputs::new(
+        proof,
+        vec![record.to_account_info().map_err(ProgramError::from)?],
+    );
+
+    cpi.invoke_light_system_program(light_cpi_accounts)
+        .map_err(ProgramError::from)?;
+
+    emit_cpi!(DepositStatusUpdatedEvent {
+        version: EVENT_VERSION,
+        address: account_meta.address,
+        status: DepositStatus::Pending,
+        dest_tx_hash: cancel_tx_hash,
+    });
+
+    Ok(())
+}
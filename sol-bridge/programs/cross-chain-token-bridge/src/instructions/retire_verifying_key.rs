@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+use crate::{error::ErrorCode, instructions::VerifyingKeyStatusEvent, state::{BridgeState, VerifyingKey, VerifyingKeyStatus, EVENT_VERSION}};
+
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(circuit_id: u32, version: u32)]
+pub struct RetireVerifyingKeyContext<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"bridge_state"],
+        bump,
+        has_one = admin @ ErrorCode::Unauthorized,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+
+    #[account(
+        mut,
+        seeds = [b"vk", circuit_id.to_le_bytes().as_ref(), version.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub verifying_key: Box<Account<'info, VerifyingKey>>,
+}
+
+pub fn retire_verifying_key_handler(
+    ctx: Context<RetireVerifyingKeyContext>,
+    circuit_id: u32,
+    version: u32,
+) -> Result<()> {
+    let verifying_key = &mut ctx.accounts.verifying_key;
+    require!(verifying_key.status != VerifyingKeyStatus::Retired, ErrorCode::InvalidVerifyingKeyStatus);
+
+    verifying_key.status = VerifyingKeyStatus::Retired;
+
+    emit_cpi!(VerifyingKeyStatusEvent {
+        version: EVENT_VERSION,
+        circuit_id,
+        vk_version: version,
+        status: VerifyingKeyStatus::Retired,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+use crate::{error::ErrorCode, state::{BridgeState, EVENT_VERSION}};
+
+#[event]
+pub struct BridgePausedEvent {
+    version: u8,
+    paused: bool,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetBridgePausedContext<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"bridge_state"],
+        bump,
+        has_one = admin @ ErrorCode::Unauthorized,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+}
+
+pub fn set_bridge_paused_handler(
+    ctx: Context<SetBridgePausedContext>,
+    paused: bool,
+) -> Result<()> {
+    ctx.accounts.bridge_state.paused = paused;
+
+    emit_cpi!(BridgePausedEvent {
+        version: EVENT_VERSION,
+        paused,
+    });
+
+    Ok(())
+}
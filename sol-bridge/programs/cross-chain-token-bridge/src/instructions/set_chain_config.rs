@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+use crate::{error::ErrorCode, state::{AddressFormat, BridgeState, ChainConfig, VerifierType, EVENT_VERSION}};
+
+#[event]
+pub struct ChainConfigSetEvent {
+    version: u8,
+    chain_id: u32,
+    enabled: bool,
+    address_format: AddressFormat,
+    confirmations: u32,
+    verifier: VerifierType,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(chain_id: u32)]
+pub struct SetChainConfigContext<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"bridge_state"],
+        bump,
+        has_one = admin @ ErrorCode::Unauthorized,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + ChainConfig::INIT_SPACE,
+        seeds = [b"chain_config", chain_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub chain_config: Account<'info, ChainConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Registers a destination chain, or updates it in place. Disabling a chain
+// stops new links and deposits to it, existing links are left untouched.
+pub fn set_chain_config_handler(
+    ctx: Context<SetChainConfigContext>,
+    chain_id: u32,
+    enabled: bool,
+    address_format: AddressFormat,
+    confirmations: u32,
+    verifier: VerifierType,
+) -> Result<()> {
+    let chain_config = &mut ctx.accounts.chain_config;
+    chain_config.chain_id = chain_id;
+    chain_config.enabled = enabled;
+    chain_config.address_format = address_format;
+    chain_config.confirmations = confirmations;
+    chain_config.verifier = verifier;
+
+    emit_cpi!(ChainConfigSetEvent {
+        version: EVENT_VERSION,
+        chain_id,
+        enabled,
+        address_format,
+        confirmations,
+        verifier,
+    });
+
+    Ok(())
+}
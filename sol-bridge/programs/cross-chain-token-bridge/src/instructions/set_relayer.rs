@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+use crate::{error::ErrorCode, state::{BridgeState, EVENT_VERSION}};
+
+#[event]
+pub struct RelayerSetEvent {
+    version: u8,
+    relayer: Pubkey,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetRelayerContext<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"bridge_state"],
+        bump,
+        has_one = admin @ ErrorCode::Unauthorized,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+}
+
+pub fn set_relayer_handler(ctx: Context<SetRelayerContext>, relayer: Pubkey) -> Result<()> {
+    ctx.accounts.bridge_state.relayer = relayer;
+
+    emit_cpi!(RelayerSetEvent {
+        version: EVENT_VERSION,
+        relayer,
+    });
+
+    Ok(())
+}
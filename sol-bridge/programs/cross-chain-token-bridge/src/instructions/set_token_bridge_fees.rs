@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+use crate::{error::ErrorCode, state::{BridgeState, TokenBridge, BPS_DENOMINATOR, EVENT_VERSION}};
+
+#[event]
+pub struct TokenBridgeFeesSetEvent {
+    version: u8,
+    token_bridge: Pubkey,
+    fee_bps: u16,
+    fee_fixed: u64,
+    relayer_fee_share_bps: u16,
+    lp_fee_share_bps: u16,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(link_hash: String)]
+pub struct SetTokenBridgeFeesContext<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"bridge_state"],
+        bump,
+        has_one = admin @ ErrorCode::Unauthorized,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+
+    #[account(
+        mut,
+        seeds=[
+            b"tb",
+            link_hash.as_bytes(),
+        ],
+        bump,
+    )]
+    pub token_bridge: Account<'info, TokenBridge>,
+}
+
+pub fn set_token_bridge_fees_handler(
+    ctx: Context<SetTokenBridgeFeesContext>,
+    _link_hash: String,
+    fee_bps: u16,
+    fee_fixed: u64,
+    relayer_fee_share_bps: u16,
+    lp_fee_share_bps: u16,
+) -> Result<()> {
+    require!(fee_bps <= BPS_DENOMINATOR, ErrorCode::InvalidArgs);
+    require!(
+        relayer_fee_share_bps as u32 + lp_fee_share_bps as u32 <= BPS_DENOMINATOR as u32,
+        ErrorCode::InvalidArgs
+    );
+
+    let token_bridge = &mut ctx.accounts.token_bridge;
+    token_bridge.fee_bps = fee_bps;
+    token_bridge.fee_fixed = fee_fixed;
+    token_bridge.relayer_fee_share_bps = relayer_fee_share_bps;
+    token_bridge.lp_fee_share_bps = lp_fee_share_bps;
+
+    emit_cpi!(TokenBridgeFeesSetEvent {
+        version: EVENT_VERSION,
+        token_bridge: ctx.accounts.token_bridge.key(),
+        fee_bps,
+        fee_fixed,
+        relayer_fee_share_bps,
+        lp_fee_share_bps,
+    });
+
+    Ok(())
+}
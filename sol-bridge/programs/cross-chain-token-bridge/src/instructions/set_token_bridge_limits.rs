@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+use crate::{error::ErrorCode, state::{BridgeState, TokenBridge, VolumeWindow, EVENT_VERSION}};
+
+#[event]
+pub struct TokenBridgeLimitsSetEvent {
+    version: u8,
+    token_bridge: Pubkey,
+    max_transfer_amount: u64,
+    window_duration: i64,
+    max_deposit_volume: u64,
+    max_withdraw_volume: u64,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(link_hash: String)]
+pub struct SetTokenBridgeLimitsContext<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"bridge_state"],
+        bump,
+        has_one = admin @ ErrorCode::Unauthorized,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+
+    #[account(
+        mut,
+        seeds=[
+            b"tb",
+            link_hash.as_bytes(),
+        ],
+        bump,
+    )]
+    pub token_bridge: Account<'info, TokenBridge>,
+}
+
+pub fn set_token_bridge_limits_handler(
+    ctx: Context<SetTokenBridgeLimitsContext>,
+    _link_hash: String,
+    max_transfer_amount: u64,
+    window_duration: i64,
+    max_deposit_volume: u64,
+    max_withdraw_volume: u64,
+) -> Result<()> {
+    let has_volume_limit = max_deposit_volume > 0 || max_withdraw_volume > 0;
+    require!(window_duration >= 0, ErrorCode::InvalidArgs);
+    require!(!has_volume_limit || window_duration > 0, ErrorCode::InvalidArgs);
+
+    let token_bridge = &mut ctx.accounts.token_bridge;
+    token_bridge.max_transfer_amount = max_transfer_amount;
+    token_bridge.window_duration = window_duration;
+    token_bridge.max_deposit_volume = max_deposit_volume;
+    token_bridge.max_withdraw_volume = max_withdraw_volume;
+    // window boundaries depend on window_duration, start counting afresh
+    token_bridge.deposit_window = VolumeWindow::default();
+    token_bridge.withdraw_window = VolumeWindow::default();
+
+    emit_cpi!(TokenBridgeLimitsSetEvent {
+        version: EVENT_VERSION,
+        token_bridge: ctx.accounts.token_bridge.key(),
+        max_transfer_amount,
+        window_duration,
+        max_deposit_volume,
+        max_withdraw_volume,
+    });
+
+    Ok(())
+}
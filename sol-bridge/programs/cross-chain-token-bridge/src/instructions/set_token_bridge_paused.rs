@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+use crate::{error::ErrorCode, state::{BridgeState, TokenBridge, EVENT_VERSION}};
+
+#[event]
+pub struct TokenBridgePausedEvent {
+    version: u8,
+    token_bridge: Pubkey,
+    paused: bool,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(
+    link_hash: String,
+    paused: bool,
+)]
+pub struct SetTokenBridgePausedContext<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"bridge_state"],
+        bump,
+        has_one = admin @ ErrorCode::Unauthorized,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+
+    #[account(
+        mut,
+        seeds=[
+            b"tb",
+            link_hash.as_bytes(),
+        ],
+        bump,
+    )]
+    pub token_bridge: Account<'info, TokenBridge>,
+}
+
+pub fn set_token_bridge_paused_handler(
+    ctx: Context<SetTokenBridgePausedContext>,
+    _link_hash: String,
+    paused: bool,
+) -> Result<()> {
+    ctx.accounts.token_bridge.paused = paused;
+
+    emit_cpi!(TokenBridgePausedEvent {
+        version: EVENT_VERSION,
+        token_bridge: ctx.accounts.token_bridge.key(),
+        paused,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+use crate::{error::ErrorCode, state::{BridgeState, TokenBridge, EVENT_VERSION}};
+
+#[event]
+pub struct TokenBridgeRefundTimeoutSetEvent {
+    version: u8,
+    token_bridge: Pubkey,
+    refund_timeout: i64,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(link_hash: String)]
+pub struct SetTokenBridgeRefundTimeoutContext<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"bridge_state"],
+        bump,
+        has_one = admin @ ErrorCode::Unauthorized,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+
+    #[account(
+        mut,
+        seeds=[
+            b"tb",
+            link_hash.as_bytes(),
+        ],
+        bump,
+    )]
+    pub token_bridge: Account<'info, TokenBridge>,
+}
+
+pub fn set_token_bridge_refund_timeout_handler(
+    ctx: Context<SetTokenBridgeRefundTimeoutContext>,
+    _link_hash: String,
+    refund_timeout: i64,
+) -> Result<()> {
+    require!(refund_timeout >= 0, ErrorCode::InvalidArgs);
+    ctx.accounts.token_bridge.refund_timeout = refund_timeout;
+
+    emit_cpi!(TokenBridgeRefundTimeoutSetEvent {
+        version: EVENT_VERSION,
+        token_bridge: ctx.accounts.token_bridge.key(),
+        refund_timeout,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, state::{BridgeStats, TokenBridge, EVENT_VERSION}};
+
+#[event]
+pub struct BridgeStatsSyncedEvent {
+    version: u8,
+    deposit_count: u128,
+    withdraw_count: u128,
+    links_synced: u32,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SyncBridgeStatsContext<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + BridgeStats::INIT_SPACE,
+        seeds = [b"bridge_stats"],
+        bump,
+    )]
+    pub bridge_stats: Account<'info, BridgeStats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Permissionless. Remaining accounts are writable TokenBridge accounts whose
+// counters are folded into BridgeStats since their last sync.
+pub fn sync_bridge_stats_handler(ctx: Context<SyncBridgeStatsContext>) -> Result<()> {
+    let bridge_stats = &mut ctx.accounts.bridge_stats;
+    for link_info in ctx.remaining_accounts {
+        require!(link_info.owner == &crate::ID && link_info.is_writable, ErrorCode::InvalidArgs);
+        let mut token_bridge = TokenBridge::try_deserialize(&mut &link_info.try_borrow_data()?[..])?;
+
+        let unsynced_deposits = token_bridge.deposit_count - token_bridge.synced_deposit_count;
+        bridge_stats.deposit_count = bridge_stats.deposit_count
+            .checked_add(unsynced_deposits as u128)
+            .ok_or(ErrorCode::MathOverflow)?;
+        token_bridge.synced_deposit_count = token_bridge.deposit_count;
+
+        let unsynced_withdraws = token_bridge.withdraw_count - token_bridge.synced_withdraw_count;
+        bridge_stats.withdraw_count = bridge_stats.withdraw_count
+            .checked_add(unsynced_withdraws as u128)
+            .ok_or(ErrorCode::MathOverflow)?;
+        token_bridge.synced_withdraw_count = token_bridge.withdraw_count;
+
+        token_bridge.try_serialize(&mut &mut link_info.try_borrow_mut_data()?[..])?;
+    }
+    bridge_stats.last_synced_at = Clock::get()?.unix_timestamp;
+
+    emit_cpi!(BridgeStatsSyncedEvent {
+        version: EVENT_VERSION,
+        deposit_count: ctx.accounts.bridge_stats.deposit_count,
+        withdraw_count: ctx.accounts.bridge_stats.withdraw_count,
+        links_synced: ctx.remaining_accounts.len() as u32,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+use light_sdk::{account::LightAccount, cpi::{CpiAccounts, CpiInputs}, instruction::account_meta::CompressedAccountMeta, ValidityProof};
+
+use crate::{error::ErrorCode, state::{BridgeState, DepositRecordCompressedAccount, DepositStatus, EVENT_VERSION}};
+
+// also emitted by reset_relayed_deposit
+#[event]
+pub struct DepositStatusUpdatedEvent {
+    pub version: u8,
+    pub address: [u8; 32],
+    pub status: DepositStatus,
+    pub dest_tx_hash: [u8; 32],
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateDepositStatusContext<'info> {
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    #[account(
+        seeds = [b"bridge_state"],
+        bump,
+        has_one = relayer @ ErrorCode::Unauthorized,
+    )]
+    pub bridge_state: Account<'info, BridgeState>,
+}
+
+// Records the relayer's progress on the destination chain so clients can
+// follow a deposit without querying it. Pending -> Relayed -> Finalized,
+// Relayed also stops the deposit from being refunded until
+// reset_relayed_deposit puts it back to Pending.
+pub fn update_deposit_status_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, UpdateDepositStatusContext<'info>>,
+    proof: ValidityProof,
+    deposit_record: DepositRecordCompressedAccount,
+    account_meta: CompressedAccountMeta,
+    status: DepositStatus,
+    dest_tx_hash: [u8; 32],
+) -> Result<()> {
+    require!(deposit_record.status.can_move_to(status), ErrorCode::InvalidDepositStatusTransition);
+    require!(dest_tx_hash != [0u8; 32], ErrorCode::InvalidArgs);
+
+    let program_id = crate::ID.into();
+    let mut record = LightAccount::<'_, DepositRecordCompressedAccount>::new_mut(
+        &program_id,
+        &account_meta,
+        deposit_record,
+    )
+    .map_err(ProgramError::from)?;
+
+    record.status = status;
+    record.dest_tx_hash = dest_tx_hash;
+
+    let light_cpi_accounts = CpiAccounts::new(
+        ctx.accounts.relayer.as_ref(),
+        ctx.remaining_accounts,
+        crate::ID,
+    )
+    .map_err(ProgramError::from)?;
+
+    let cpi = CpiInputs::new(
+        proof,
+        vec![record.to_account_info().map_err(ProgramError::from)?],
+    );
+
+    cpi.invoke_light_system_program(light_cpi_accounts)
+        .map_err(ProgramError::from)?;
+
+    emit_cpi!(DepositStatusUpdatedEvent {
+        version: EVENT_VERSION,
+        address: account_meta.address,
+        status,
+        dest_tx_hash,
+    });
+
+    Ok(())
+}
@@ -1,54 +1,120 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, transfer_checked, TransferChecked}};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{transfer_checked, mint_to, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked}};
 use light_sdk::{account::LightAccount, address::v1::derive_address, cpi::{CpiAccounts, CpiInputs}, instruction::merkle_context::PackedAddressMerkleContext, NewAddressParamsPacked, ValidityProof};
 
-use crate::{error::ErrorCode, state::{BridgeState, TokenBridge, WithdrawalProof, WithdrawalRecordCompressedAccount, SOURCE_CHAIN_ID}, zk::{groth16_verifier, ETHDEPOSIT_VERIFYINGKEY}};
+use crate::{error::ErrorCode, state::{BridgeState, ChainConfig, LiquidityPool, RelayerBalance, TokenBridge, TokenBridgeMode, VerifyingKey, VerifyingKeyStatus, WithdrawalProof, WithdrawalRecordCompressedAccount, EVENT_VERSION, SOURCE_CHAIN_ID}, zk::{groth16_verifier, mint_addr_to_field, withdrawal_public_inputs}};
 
+#[event]
+pub struct WithdrawEvent {
+    version: u8,
+    address: [u8; 32],
+    token_bridge: Pubkey,
+    // dense per link, matches the record's withdrawalId
+    withdrawal_id: u64,
+    nullifier: [u8; 32],
+    recipient: Pubkey,
+    mint: Pubkey,
+    // in the canonical bridge unit
+    amount: u64,
+    // in the mint's unit
+    fee: u64,
+    // in the source chain's unit, below one canonical unit and not released here
+    dust: u128,
+}
+
+// accounts shared by withdraw and withdraw_with_proof
 #[derive(Accounts)]
 #[instruction(
     proof: ValidityProof,
     address_merkle_context: PackedAddressMerkleContext,
     output_merkle_tree_index: u8,
-    amount: u64,
+    amount: u128,
     link_hash: String,
     nullifier: [u8; 32],
+    vk_version: u32,
 )]
-pub struct WithdrawContext<'info> {
+pub struct WithdrawAccounts<'info> {
     #[account(mut)]
     pub relayer: Signer<'info>,
 
+    #[account(mut)]
     pub mint: InterfaceAccount<'info, Mint>,
 
     #[account(
-        seeds = [b"withdrawal_proof", nullifier.as_ref()],
-        bump
+        seeds = [b"chain_config", token_bridge.source_chain.to_le_bytes().as_ref()],
+        bump,
+        constraint = source_chain_config.enabled @ ErrorCode::ChainNotEnabled,
     )]
-    pub withdrawal_proof: Account<'info, WithdrawalProof>,
+    pub source_chain_config: Box<Account<'info, ChainConfig>>,
+
+    // the source chain's verifier picks the circuit
+    #[account(
+        seeds = [b"vk", source_chain_config.groth16_circuit_id()?.to_le_bytes().as_ref(), vk_version.to_le_bytes().as_ref()],
+        bump,
+        constraint = verifying_key.status == VerifyingKeyStatus::Active @ ErrorCode::VerifyingKeyNotActive,
+    )]
+    pub verifying_key: Box<Account<'info, VerifyingKey>>,
 
+    // only the relayer may spend a proof, the circuit doesn't attest the EVM deposit
     #[account(
         mut,
         seeds = [b"bridge_state"],
-        bump
+        bump,
+        has_one = relayer @ ErrorCode::Unauthorized,
     )]
     pub bridge_state: Account<'info, BridgeState>,
 
     #[account(
+        mut,
         seeds=[
             b"tb",
-            link_hash.as_bytes().as_ref(),
+            link_hash.as_bytes(),
         ],
         bump,
     )]
     pub token_bridge: Account<'info, TokenBridge>,
 
+    /// CHECK: seeds only, see TokenBridgeMode::MintBurn
+    #[account(
+        seeds = [b"mint_authority"],
+        bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
     #[account(
         mut,
         token::mint = mint,
         token::authority = token_vault,
-        seeds = [b"vault", mint.key().as_ref()],
+        seeds = [b"vault", token_bridge.vault_link_hash().as_bytes()],
+        bump,
+    )]
+    pub token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", token_bridge.vault_link_hash().as_bytes()],
+        bump,
+    )]
+    pub liquidity_pool: Option<Box<Account<'info, LiquidityPool>>>,
+
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        token::mint = mint,
+        token::authority = fee_vault,
+        seeds = [b"fee_vault", mint.key().as_ref()],
+        bump,
+    )]
+    pub fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = 8 + RelayerBalance::INIT_SPACE,
+        seeds = [b"relayer_balance", relayer.key().as_ref(), mint.key().as_ref()],
         bump,
     )]
-    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+    pub relayer_balance: Box<Account<'info, RelayerBalance>>,
 
     /// CHECK: person who needs to receive the tokens
     pub recipient: UncheckedAccount<'info>,
@@ -67,66 +133,229 @@ pub struct WithdrawContext<'info> {
     pub system_program: Program<'info, System>,
 }
 
+// no #[instruction] here: deserializing the args would advance the data the
+// nested WithdrawAccounts reads its own args from
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawContext<'info> {
+    pub withdraw: WithdrawAccounts<'info>,
+
+    // checked against the nullifier argument in the handler.
+    // Closed once the withdrawal succeeds, the rent goes back to the relayer
+    #[account(
+        mut,
+        seeds = [b"withdrawal_proof", withdraw.relayer.key().as_ref(), withdrawal_proof.nullifier.as_ref()],
+        bump,
+        constraint = withdrawal_proof.creator == withdraw.relayer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub withdrawal_proof: Account<'info, WithdrawalProof>,
+}
+
+// groth16 proof and the public inputs that are not derived from the accounts
+pub(crate) struct Groth16WithdrawalProof {
+    pub proof_a: [u8; 64],
+    pub proof_b: [u8; 128],
+    pub proof_c: [u8; 64],
+    pub old_root: [u8; 32],
+    pub new_root: [u8; 32],
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn withdraw_handler<'info>(
     ctx: Context<'_,'_,'_, 'info, WithdrawContext<'info>>,
     proof: ValidityProof,
     address_merkle_context: PackedAddressMerkleContext,
     output_merkle_tree_index: u8,
-    amount: u64,
-    link_hash: String,
+    amount: u128,
+    _link_hash: String,
     nullifier: [u8; 32],
+    _vk_version: u32,
 ) -> Result<()> {
-    require!(amount > 0, ErrorCode::WithdrawAmountShouldBeGreaterThanZero);
     let withdrawal_proof = &ctx.accounts.withdrawal_proof;
-    msg!("Verifying withdrawal proof with proof_a: {:?}, proof_b: {:?}, proof_c: {:?}, nullifier: {:?}, new_root: {:?}", 
-         withdrawal_proof.proof_a, 
-         withdrawal_proof.proof_b, 
-         withdrawal_proof.proof_c, 
-         withdrawal_proof.nullifier, 
-         withdrawal_proof.new_root);
-    groth16_verifier(withdrawal_proof.proof_a, withdrawal_proof.proof_b, withdrawal_proof.proof_c, &[withdrawal_proof.nullifier, withdrawal_proof.new_root], ETHDEPOSIT_VERIFYINGKEY);
-
-    let transfer_checked_t = TransferChecked {
-        authority: ctx.accounts.token_vault.to_account_info(),
-        from: ctx.accounts.token_vault.to_account_info(),
-        mint: ctx.accounts.mint.to_account_info(),
-        to: ctx.accounts.user_ata.to_account_info(),
+    require!(withdrawal_proof.nullifier == nullifier, ErrorCode::InvalidProofData);
+    let groth16_proof = Groth16WithdrawalProof {
+        proof_a: withdrawal_proof.proof_a,
+        proof_b: withdrawal_proof.proof_b,
+        proof_c: withdrawal_proof.proof_c,
+        old_root: withdrawal_proof.old_root,
+        new_root: withdrawal_proof.new_root,
     };
+    let withdraw_event = process_withdraw(
+        &mut ctx.accounts.withdraw,
+        &ctx.bumps.withdraw,
+        ctx.remaining_accounts,
+        proof,
+        address_merkle_context,
+        output_merkle_tree_index,
+        amount,
+        nullifier,
+        groth16_proof,
+    )?;
+    ctx.accounts.withdrawal_proof.close(ctx.accounts.withdraw.relayer.to_account_info())?;
 
-    let mint_key = ctx.accounts.mint.key();
-    let signer_seeds: &[&[&[u8]]] = &[
-        &[
-            b"vault",
-            mint_key.as_ref(),
-            &[ctx.bumps.token_vault]
-        ],
-    ];
+    emit_cpi!(withdraw_event);
 
-    let transfer_checked_cpi = CpiContext::new_with_signer(
-        ctx.accounts.token_program.to_account_info(),
-        transfer_checked_t,
-        signer_seeds
-    );
+    Ok(())
+}
 
-    let res = transfer_checked(
-        transfer_checked_cpi,
+// shared by withdraw and withdraw_with_proof once the groth16 proof is known
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn process_withdraw<'info>(
+    accounts: &mut WithdrawAccounts<'info>,
+    bumps: &WithdrawAccountsBumps,
+    remaining_accounts: &[AccountInfo<'info>],
+    proof: ValidityProof,
+    address_merkle_context: PackedAddressMerkleContext,
+    output_merkle_tree_index: u8,
+    amount: u128,
+    nullifier: [u8; 32],
+    groth16_proof: Groth16WithdrawalProof,
+) -> Result<WithdrawEvent> {
+    require!(amount > 0, ErrorCode::WithdrawAmountShouldBeGreaterThanZero);
+    require!(!accounts.bridge_state.paused, ErrorCode::BridgePaused);
+    require!(!accounts.token_bridge.paused, ErrorCode::TokenBridgePaused);
+    require!(accounts.token_bridge.dest_chain == SOURCE_CHAIN_ID, ErrorCode::InvalidArgs);
+    accounts.token_bridge.check_solana_mint(&accounts.mint.key())?;
+    require!(accounts.mint.decimals == accounts.token_bridge.dest_decimals, ErrorCode::MintDecimalsMismatch);
+    // amount is proven in the source chain's unit. The EVM bridge never pulls
+    // dust below the canonical unit, any that shows up is reported in WithdrawEvent
+    let source_decimals = accounts.token_bridge.source_decimals;
+    let (canonical_amount, dust) = accounts.token_bridge.normalise_amount(amount, source_decimals)?;
+    require!(canonical_amount > 0, ErrorCode::WithdrawAmountShouldBeGreaterThanZero);
+    let local_amount = accounts.token_bridge.denormalise_amount(canonical_amount, accounts.mint.decimals)?;
+    accounts.token_bridge.record_withdraw(Clock::get()?.unix_timestamp, local_amount)?;
+    // the proof inserts the nullifier into the tree the program last saw,
+    // so a nullifier that is already in it can never be inserted twice
+    require!(groth16_proof.old_root == accounts.bridge_state.nullifier_root, ErrorCode::StaleNullifierRoot);
+    // amount, recipient and mint are public inputs, so the proof authorises exactly this transfer,
+    // and the deposit's source chain and token pin it to this link
+    let public_inputs = withdrawal_public_inputs(
+        nullifier,
+        groth16_proof.new_root,
+        accounts.token_bridge.source_chain,
+        &accounts.recipient.key(),
+        &accounts.mint.key(),
+        mint_addr_to_field(&accounts.token_bridge.source_chain_mint_addr)?,
         amount,
-        ctx.accounts.mint.decimals
+        groth16_proof.old_root,
     );
+    groth16_verifier(groth16_proof.proof_a, groth16_proof.proof_b, groth16_proof.proof_c, &public_inputs, accounts.verifying_key.to_groth16())?;
+    accounts.bridge_state.nullifier_root = groth16_proof.new_root;
+
+    let fee = accounts.token_bridge.fee_for(local_amount)?;
+    // the LP share of the fee is left in the vault for the pool
+    let lp_fee = accounts.token_bridge.lp_share_of(fee);
+    let fee_balance_before = accounts.fee_vault.amount;
+
+    match accounts.token_bridge.mode {
+        TokenBridgeMode::LockRelease => {
+            let token_vault = accounts.token_vault.as_ref().ok_or(ErrorCode::TokenVaultRequired)?;
+            let vault_link_hash = accounts.token_bridge.vault_link_hash();
+            let signer_seeds: &[&[&[u8]]] = &[
+                &[
+                    b"vault",
+                    vault_link_hash.as_bytes(),
+                    &[bumps.token_vault.unwrap()]
+                ],
+            ];
+
+            let transfer_checked_t = TransferChecked {
+                authority: token_vault.to_account_info(),
+                from: token_vault.to_account_info(),
+                mint: accounts.mint.to_account_info(),
+                to: accounts.user_ata.to_account_info(),
+            };
+            transfer_checked(
+                CpiContext::new_with_signer(accounts.token_program.to_account_info(), transfer_checked_t, signer_seeds),
+                local_amount - fee,
+                accounts.mint.decimals,
+            )?;
+
+            if fee > lp_fee {
+                let fee_transfer_t = TransferChecked {
+                    authority: token_vault.to_account_info(),
+                    from: token_vault.to_account_info(),
+                    mint: accounts.mint.to_account_info(),
+                    to: accounts.fee_vault.to_account_info(),
+                };
+                transfer_checked(
+                    CpiContext::new_with_signer(accounts.token_program.to_account_info(), fee_transfer_t, signer_seeds),
+                    fee - lp_fee,
+                    accounts.mint.decimals,
+                )?;
+            }
+        }
+        TokenBridgeMode::MintBurn => {
+            require!(
+                accounts.mint.mint_authority == Some(accounts.mint_authority.key()).into(),
+                ErrorCode::MintNotOwnedByBridge
+            );
+            let signer_seeds: &[&[&[u8]]] = &[
+                &[
+                    b"mint_authority",
+                    &[bumps.mint_authority]
+                ],
+            ];
+
+            let mint_to_t = MintTo {
+                authority: accounts.mint_authority.to_account_info(),
+                mint: accounts.mint.to_account_info(),
+                to: accounts.user_ata.to_account_info(),
+            };
+            mint_to(
+                CpiContext::new_with_signer(accounts.token_program.to_account_info(), mint_to_t, signer_seeds),
+                local_amount - fee,
+            )?;
+
+            if fee > 0 {
+                let fee_mint_to_t = MintTo {
+                    authority: accounts.mint_authority.to_account_info(),
+                    mint: accounts.mint.to_account_info(),
+                    to: accounts.fee_vault.to_account_info(),
+                };
+                mint_to(
+                    CpiContext::new_with_signer(accounts.token_program.to_account_info(), fee_mint_to_t, signer_seeds),
+                    fee,
+                )?;
+            }
+        }
+    }
+
+    if accounts.token_bridge.mode == TokenBridgeMode::LockRelease {
+        // lp_fee never leaves the vault
+        accounts.token_bridge.add_released(local_amount - lp_fee)?;
+        let liquidity_pool = accounts.liquidity_pool.as_mut().ok_or(ErrorCode::TokenVaultRequired)?;
+        liquidity_pool.pay_out(local_amount - lp_fee)?;
+        liquidity_pool.add_liquidity(lp_fee)?;
+    }
+
+    // the fee stays in the fee vault, split between the protocol and this relayer.
+    // Credited from the balance delta, a token-2022 transfer fee is never claimable.
+    accounts.fee_vault.reload()?;
+    let fee_received = accounts.fee_vault.amount.checked_sub(fee_balance_before).ok_or(ErrorCode::MathOverflow)?;
+    let relayer_fee = accounts.token_bridge.relayer_share_of(fee).min(fee_received);
+    let token_bridge = &mut accounts.token_bridge;
+    token_bridge.protocol_fees_accrued = token_bridge.protocol_fees_accrued
+        .checked_add(fee_received - relayer_fee)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let relayer_balance = &mut accounts.relayer_balance;
+    relayer_balance.relayer = accounts.relayer.key();
+    relayer_balance.mint = accounts.mint.key();
+    relayer_balance.amount = relayer_balance.amount.checked_add(relayer_fee).ok_or(ErrorCode::MathOverflow)?;
 
     let program_id = crate::ID.into();
     let light_cpi_accounts = CpiAccounts::new(
-        ctx.accounts.relayer.as_ref(),
-        ctx.remaining_accounts,
+        accounts.relayer.as_ref(),
+        remaining_accounts,
         crate::ID,
     ).map_err(ProgramError::from)?;
 
-    let current_withdrawl_num = ctx.accounts.bridge_state.withdraw_count.checked_add(1).unwrap();
-
-    ctx.accounts.bridge_state.withdraw_count = current_withdrawl_num.clone();
+    let withdrawal_id = accounts.token_bridge.next_withdrawal_id()?;
 
+    // keyed by the nullifier alone: the address tree rejects a second record for
+    // the same nullifier, whichever recipient the replay names
     let (address, address_seed) = derive_address(
-        &[b"withdrawal", ctx.accounts.recipient.key().as_ref(), nullifier.as_ref()],
+        &[b"withdrawal", nullifier.as_ref()],
         &light_cpi_accounts.tree_accounts()[address_merkle_context.address_merkle_tree_pubkey_index as usize].key(),
         &crate::ID);
 
@@ -143,37 +372,39 @@ pub fn withdraw_handler<'info>(
         output_merkle_tree_index,
     );
 
-    let token_bridge = &ctx.accounts.token_bridge;
+    let token_bridge = &accounts.token_bridge;
 
     // withdrawl_record.depositer = depositer;
     withdrawl_record.sourceChainId = token_bridge.source_chain as u64;
     withdrawl_record.destChainId = SOURCE_CHAIN_ID as u64;
-    withdrawl_record.destChainAddr = ctx.accounts.recipient.key();
-    withdrawl_record.destChainMintAddr = ctx.accounts.mint.key();
+    withdrawl_record.destChainAddr = accounts.recipient.key();
+    withdrawl_record.destChainMintAddr = accounts.mint.key();
     withdrawl_record.tokenMint = token_bridge.source_chain_mint_addr.clone();
-    withdrawl_record.amount = amount;
+    withdrawl_record.amount = canonical_amount;
+    withdrawl_record.fee = fee;
     withdrawl_record.timestamp = Clock::get()?.unix_timestamp;
-    withdrawl_record.withdrawalId = ctx.accounts.bridge_state.withdraw_count;
+    withdrawl_record.withdrawalId = withdrawal_id as u128;
 
-    ctx.accounts.bridge_state.withdraw_count = current_withdrawl_num.checked_add(1).unwrap();
-
-    msg!("withdrawl_record: {:?}", withdrawl_record);
+    let withdraw_event = WithdrawEvent {
+        version: EVENT_VERSION,
+        address,
+        token_bridge: accounts.token_bridge.key(),
+        withdrawal_id,
+        nullifier,
+        recipient: accounts.recipient.key(),
+        mint: accounts.mint.key(),
+        amount: canonical_amount,
+        fee,
+        dust,
+    };
 
     let cpi = CpiInputs::new_with_address(
         proof,
-        vec![withdrawl_record.to_account_info().map_err(|e| {
-            msg!("Error converting withdrawal record to account info: {:?}", e);
-            ProgramError::from(e)
-        })?],
+        vec![withdrawl_record.to_account_info().map_err(ProgramError::from)?],
         vec![new_address_params],
     );
 
-    msg!("withdrawal_record addr: {:?}", address);
+    cpi.invoke_light_system_program(light_cpi_accounts).map_err(ProgramError::from)?;
 
-    cpi.invoke_light_system_program(light_cpi_accounts).map_err(|e| {
-        msg!("err invoking light sys program: {:?}", e);
-        ProgramError::from(e)
-    })?;
-
-    Ok(())
+    Ok(withdraw_event)
 }
\ No newline at end of file
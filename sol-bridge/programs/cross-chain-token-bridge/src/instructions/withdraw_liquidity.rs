@@ -0,0 +1,142 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{transfer_checked, burn, Burn, Mint, TokenAccount, TokenInterface, TransferChecked}};
+
+use crate::{error::ErrorCode, state::{BridgeState, LiquidityPool, TokenBridge, EVENT_VERSION}};
+
+#[event]
+pub struct LiquidityWithdrawnEvent {
+    version: u8,
+    token_bridge: Pubkey,
+    provider: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+    shares: u64,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(link_hash: String)]
+pub struct WithdrawLiquidityContext<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"bridge_state"],
+        bump
+    )]
+    pub bridge_state: Box<Account<'info, BridgeState>>,
+
+    #[account(
+        mut,
+        seeds=[
+            b"tb",
+            link_hash.as_bytes(),
+        ],
+        bump,
+    )]
+    pub token_bridge: Box<Account<'info, TokenBridge>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = token_vault,
+        seeds = [b"vault", token_bridge.vault_link_hash().as_bytes()],
+        bump
+    )]
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", token_bridge.vault_link_hash().as_bytes()],
+        bump,
+    )]
+    pub liquidity_pool: Box<Account<'info, LiquidityPool>>,
+
+    #[account(
+        mut,
+        seeds = [b"lp_mint", token_bridge.vault_link_hash().as_bytes()],
+        bump,
+    )]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = lp_mint,
+        associated_token::authority = signer,
+    )]
+    pub user_lp_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::authority = signer,
+        associated_token::mint = mint
+    )]
+    pub user_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Redeems pool shares for their part of total_liquidity, limited to what
+// the vault holds above depositor collateral.
+pub fn withdraw_liquidity_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, WithdrawLiquidityContext<'info>>,
+    _link_hash: String,
+    shares: u64,
+) -> Result<()> {
+    require!(!ctx.accounts.bridge_state.paused, ErrorCode::BridgePaused);
+    require!(!ctx.accounts.token_bridge.paused, ErrorCode::TokenBridgePaused);
+    require!(shares > 0, ErrorCode::InvalidArgs);
+    ctx.accounts.token_bridge.check_solana_mint(&ctx.accounts.mint.key())?;
+
+    let liquidity_pool = &mut ctx.accounts.liquidity_pool;
+    let amount = liquidity_pool.amount_for(shares, ctx.accounts.lp_mint.supply)?;
+    require!(amount > 0, ErrorCode::InvalidArgs);
+    require!(amount <= liquidity_pool.available(ctx.accounts.token_vault.amount), ErrorCode::InsufficientLiquidity);
+    liquidity_pool.total_liquidity = liquidity_pool.total_liquidity.checked_sub(amount).ok_or(ErrorCode::MathOverflow)?;
+    ctx.accounts.token_bridge.add_released(amount)?;
+
+    let burn_t = Burn {
+        authority: ctx.accounts.signer.to_account_info(),
+        from: ctx.accounts.user_lp_ata.to_account_info(),
+        mint: ctx.accounts.lp_mint.to_account_info(),
+    };
+    burn(CpiContext::new(ctx.accounts.token_program.to_account_info(), burn_t), shares)?;
+
+    let mint_key = ctx.accounts.mint.key();
+    let vault_link_hash = ctx.accounts.token_bridge.vault_link_hash();
+    let signer_seeds: &[&[&[u8]]] = &[
+        &[
+            b"vault",
+            vault_link_hash.as_bytes(),
+            &[ctx.bumps.token_vault]
+        ],
+    ];
+
+    let transfer_checked_t = TransferChecked {
+        authority: ctx.accounts.token_vault.to_account_info(),
+        from: ctx.accounts.token_vault.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        to: ctx.accounts.user_ata.to_account_info(),
+    };
+    transfer_checked(
+        CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), transfer_checked_t, signer_seeds),
+        amount,
+        ctx.accounts.mint.decimals,
+    )?;
+
+    emit_cpi!(LiquidityWithdrawnEvent {
+        version: EVENT_VERSION,
+        token_bridge: ctx.accounts.token_bridge.key(),
+        provider: ctx.accounts.signer.key(),
+        mint: mint_key,
+        amount,
+        shares,
+    });
+
+    Ok(())
+}
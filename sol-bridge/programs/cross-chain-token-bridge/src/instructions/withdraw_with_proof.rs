@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+use light_sdk::{instruction::merkle_context::PackedAddressMerkleContext, ValidityProof};
+
+// the glob brings in the client modules and bumps the Accounts derive expects for WithdrawAccounts
+use crate::instructions::withdraw::*;
+
+// The groth16 proof travels in the instruction data instead of a withdrawal_proof
+// account, so the withdrawal fits in one transaction.
+// The light system accounts are expected to come from an address lookup table.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawWithProofContext<'info> {
+    pub withdraw: WithdrawAccounts<'info>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_with_proof_handler<'info>(
+    ctx: Context<'_,'_,'_, 'info, WithdrawWithProofContext<'info>>,
+    proof: ValidityProof,
+    address_merkle_context: PackedAddressMerkleContext,
+    output_merkle_tree_index: u8,
+    amount: u128,
+    _link_hash: String,
+    nullifier: [u8; 32],
+    _vk_version: u32,
+    proof_a: [u8; 64],
+    proof_b: [u8; 128],
+    proof_c: [u8; 64],
+    old_root: [u8; 32],
+    new_root: [u8; 32],
+) -> Result<()> {
+    let withdraw_event = process_withdraw(
+        &mut ctx.accounts.withdraw,
+        &ctx.bumps.withdraw,
+        ctx.remaining_accounts,
+        proof,
+        address_merkle_context,
+        output_merkle_tree_index,
+        amount,
+        nullifier,
+        Groth16WithdrawalProof { proof_a, proof_b, proof_c, old_root, new_root },
+    )?;
+
+    emit_cpi!(withdraw_event);
+
+    Ok(())
+}
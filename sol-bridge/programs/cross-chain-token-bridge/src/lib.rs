@@ -1,3 +1,8 @@
+// #[program] emits its IDL instructions next to the program module, at the
+// crate root, and their resize still calls AccountInfo::realloc. Only that
+// generated code is covered here; the program's own uses allow it in place.
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
 use light_sdk::{
     account::LightAccount,
@@ -9,10 +14,11 @@ use light_sdk::{
     LightDiscriminator, LightHasher, NewAddressParamsPacked, ValidityProof,
 };
 mod state;
-mod zk;
+pub mod zk;
 mod instructions;
 mod error;
 use instructions::*;
+use state::{AddressFormat, DepositRecordCompressedAccount, DepositStatus, DestinationAddress, TokenBridgeMode, VerifierType};
 
 declare_id!("82ZuVtSrqVWfmuxH34R9ASdwLJ6TTNxGyBeBXbeZMycP");
 
@@ -21,23 +27,137 @@ pub mod cross_chain_token_bridge {
 
     use super::*;
 
-    pub fn init(ctx: Context<InitContext>) -> Result<()> {
-        return init_handler(ctx);
+    pub fn init(ctx: Context<InitContext>, nullifier_root: [u8; 32]) -> Result<()> {
+        init_handler(ctx, nullifier_root)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn init_withdrawal_proof_account(ctx: Context<InitWithdrawalProofAccountContext>, withdrawal_id: u128, proof_a: [u8; 64], proof_b: [u8; 128], proof_c: [u8; 64], nullifier: [u8; 32], old_root: [u8; 32], new_root: [u8; 32]) -> Result<()> {
+        init_withdrawal_proof_account_handler(ctx, withdrawal_id, proof_a, proof_b, proof_c, nullifier, old_root, new_root)
     }
 
-    pub fn init_withdrawal_proof_account(ctx: Context<InitWithdrawalProofAccountContext>, withdrawal_id: u128, proof_a: [u8; 64], proof_b: [u8; 128], proof_c: [u8; 64], nullifier: [u8; 32], new_root: [u8; 32]) -> Result<()> {
-        return init_withdrawal_proof_account_handler(ctx, withdrawal_id, proof_a, proof_b, proof_c, nullifier, new_root);
+    pub fn close_withdrawal_proof(ctx: Context<CloseWithdrawalProofContext>, nullifier: [u8; 32]) -> Result<()> {
+        close_withdrawal_proof_handler(ctx, nullifier)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn init_token_bridge(
         ctx: Context<InitTokenBridgeContext>,
         source_chain: u32,
         source_chain_mint_addr: String,
         dest_chain: u32,
         dest_chain_mint_addr: String,
+        mode: TokenBridgeMode,
+        source_decimals: u8,
+        dest_decimals: u8,
+    ) -> Result<()> {
+        init_token_bridge_handler(
+            ctx,
+            source_chain,
+            source_chain_mint_addr,
+            dest_chain,
+            dest_chain_mint_addr,
+            mode,
+            source_decimals,
+            dest_decimals,
+        )
+    }
+
+    pub fn create_wrapped_mint(ctx: Context<CreateWrappedMintContext>, decimals: u8) -> Result<()> {
+        create_wrapped_mint_handler(ctx, decimals)
+    }
+
+    pub fn propose_admin(ctx: Context<ProposeAdminContext>, new_admin: Pubkey) -> Result<()> {
+        propose_admin_handler(ctx, new_admin)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdminContext>) -> Result<()> {
+        accept_admin_handler(ctx)
+    }
+
+    pub fn set_bridge_paused(ctx: Context<SetBridgePausedContext>, paused: bool) -> Result<()> {
+        set_bridge_paused_handler(ctx, paused)
+    }
+
+    pub fn set_token_bridge_paused(
+        ctx: Context<SetTokenBridgePausedContext>,
+        link_hash: String,
+        paused: bool,
+    ) -> Result<()> {
+        set_token_bridge_paused_handler(ctx, link_hash, paused)
+    }
+
+    pub fn set_token_bridge_limits(
+        ctx: Context<SetTokenBridgeLimitsContext>,
+        link_hash: String,
+        max_transfer_amount: u64,
+        window_duration: i64,
+        max_deposit_volume: u64,
+        max_withdraw_volume: u64,
+    ) -> Result<()> {
+        set_token_bridge_limits_handler(ctx, link_hash, max_transfer_amount, window_duration, max_deposit_volume, max_withdraw_volume)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn register_verifying_key(
+        ctx: Context<RegisterVerifyingKeyContext>,
+        circuit_id: u32,
+        version: u32,
+        nr_public_inputs: u8,
+        vk_alpha_g1: [u8; 64],
+        vk_beta_g2: [u8; 128],
+        vk_gamma_g2: [u8; 128],
+        vk_delta_g2: [u8; 128],
+        vk_ic: Vec<[u8; 64]>,
+    ) -> Result<()> {
+        register_verifying_key_handler(ctx, circuit_id, version, nr_public_inputs, vk_alpha_g1, vk_beta_g2, vk_gamma_g2, vk_delta_g2, vk_ic)
+    }
+
+    pub fn append_verifying_key_ic(
+        ctx: Context<AppendVerifyingKeyIcContext>,
+        circuit_id: u32,
+        version: u32,
+        vk_ic: Vec<[u8; 64]>,
+    ) -> Result<()> {
+        append_verifying_key_ic_handler(ctx, circuit_id, version, vk_ic)
+    }
+
+    pub fn activate_verifying_key(ctx: Context<ActivateVerifyingKeyContext>, circuit_id: u32, version: u32) -> Result<()> {
+        activate_verifying_key_handler(ctx, circuit_id, version)
+    }
+
+    pub fn retire_verifying_key(ctx: Context<RetireVerifyingKeyContext>, circuit_id: u32, version: u32) -> Result<()> {
+        retire_verifying_key_handler(ctx, circuit_id, version)
+    }
+
+    pub fn set_chain_config(
+        ctx: Context<SetChainConfigContext>,
+        chain_id: u32,
+        enabled: bool,
+        address_format: AddressFormat,
+        confirmations: u32,
+        verifier: VerifierType,
+    ) -> Result<()> {
+        set_chain_config_handler(ctx, chain_id, enabled, address_format, confirmations, verifier)
+    }
+
+    pub fn set_token_bridge_fees(
+        ctx: Context<SetTokenBridgeFeesContext>,
         link_hash: String,
+        fee_bps: u16,
+        fee_fixed: u64,
+        relayer_fee_share_bps: u16,
+        lp_fee_share_bps: u16,
     ) -> Result<()> {
-        return init_token_bridge_handler(ctx, source_chain, source_chain_mint_addr, dest_chain, dest_chain_mint_addr, link_hash);
+        set_token_bridge_fees_handler(ctx, link_hash, fee_bps, fee_fixed, relayer_fee_share_bps, lp_fee_share_bps)
+    }
+
+    pub fn claim_protocol_fees(ctx: Context<ClaimProtocolFeesContext>, link_hash: String) -> Result<()> {
+        claim_protocol_fees_handler(ctx, link_hash)
+    }
+
+    pub fn claim_relayer_fees(ctx: Context<ClaimRelayerFeesContext>) -> Result<()> {
+        claim_relayer_fees_handler(ctx)
     }
     
     pub fn deposit<'info> (
@@ -47,28 +167,145 @@ pub mod cross_chain_token_bridge {
         output_merkle_tree_index: u8,
         amount: u64,
         link_hash: String,
-        dest_chain_addr: String,
+        dest_chain_addr: DestinationAddress,
+    ) -> Result<()> {
+        deposit_handler(
+            ctx,
+            proof,
+            address_merkle_context,
+            output_merkle_tree_index,
+            amount,
+            link_hash,
+            dest_chain_addr,
+        )
+    }
+
+    pub fn set_token_bridge_refund_timeout(
+        ctx: Context<SetTokenBridgeRefundTimeoutContext>,
+        link_hash: String,
+        refund_timeout: i64,
+    ) -> Result<()> {
+        set_token_bridge_refund_timeout_handler(ctx, link_hash, refund_timeout)
+    }
+
+    pub fn refund_deposit<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefundDepositContext<'info>>,
+        proof: ValidityProof,
+        link_hash: String,
+        deposit_record: DepositRecordCompressedAccount,
+        account_meta: CompressedAccountMeta,
+    ) -> Result<()> {
+        refund_deposit_handler(ctx, proof, link_hash, deposit_record, account_meta)
+    }
+
+    pub fn withdraw_liquidity<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawLiquidityContext<'info>>,
+        link_hash: String,
+        shares: u64,
+    ) -> Result<()> {
+        withdraw_liquidity_handler(ctx, link_hash, shares)
+    }
+
+    pub fn check_solvency(
+        ctx: Context<CheckSolvencyContext>,
+        link_hash: String,
+        pause_if_insolvent: bool,
+    ) -> Result<()> {
+        check_solvency_handler(ctx, link_hash, pause_if_insolvent)
+    }
+
+    pub fn sync_bridge_stats(ctx: Context<SyncBridgeStatsContext>) -> Result<()> {
+        sync_bridge_stats_handler(ctx)
+    }
+
+    pub fn set_relayer(ctx: Context<SetRelayerContext>, relayer: Pubkey) -> Result<()> {
+        set_relayer_handler(ctx, relayer)
+    }
+
+    pub fn update_deposit_status<'info>(
+        ctx: Context<'_, '_, '_, 'info, UpdateDepositStatusContext<'info>>,
+        proof: ValidityProof,
+        deposit_record: DepositRecordCompressedAccount,
+        account_meta: CompressedAccountMeta,
+        status: DepositStatus,
+        dest_tx_hash: [u8; 32],
+    ) -> Result<()> {
+        update_deposit_status_handler(ctx, proof, deposit_record, account_meta, status, dest_tx_hash)
+    }
+
+    pub fn reset_relayed_deposit<'info>(
+        ctx: Context<'_, '_, '_, 'info, ResetRelayedDepositContext<'info>>,
+        proof: ValidityProof,
+        link_hash: String,
+        deposit_record: DepositRecordCompressedAccount,
+        account_meta: CompressedAccountMeta,
+        cancel_tx_hash: [u8; 32],
     ) -> Result<()> {
-        return deposit_handler(ctx, proof, address_merkle_context, output_merkle_tree_index, amount, link_hash, dest_chain_addr);
+        reset_relayed_deposit_handler(ctx, proof, link_hash, deposit_record, account_meta, cancel_tx_hash)
     }
 
-    pub fn deposit_to_vault(
-        ctx: Context<DepositToVaultContext>,
+    pub fn deposit_to_vault<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositToVaultContext<'info>>,
+        link_hash: String,
         amount: u64
     ) -> Result<()> {
-        return deposit_to_vault_handler(ctx, amount);
+        deposit_to_vault_handler(ctx, link_hash, amount)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn withdraw<'info>(
         ctx: Context<'_,'_,'_, 'info, WithdrawContext<'info>>,
         proof: ValidityProof,
         address_merkle_context: PackedAddressMerkleContext,
         output_merkle_tree_index: u8,
-        amount: u64,
+        amount: u128,
         link_hash: String,
         nullifier: [u8; 32],
+        vk_version: u32,
     ) -> Result<()> {
-        return withdraw_handler(ctx, proof, address_merkle_context, output_merkle_tree_index, amount, link_hash, nullifier);
+        withdraw_handler(
+            ctx,
+            proof,
+            address_merkle_context,
+            output_merkle_tree_index,
+            amount,
+            link_hash,
+            nullifier,
+            vk_version,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn withdraw_with_proof<'info>(
+        ctx: Context<'_,'_,'_, 'info, WithdrawWithProofContext<'info>>,
+        proof: ValidityProof,
+        address_merkle_context: PackedAddressMerkleContext,
+        output_merkle_tree_index: u8,
+        amount: u128,
+        link_hash: String,
+        nullifier: [u8; 32],
+        vk_version: u32,
+        proof_a: [u8; 64],
+        proof_b: [u8; 128],
+        proof_c: [u8; 64],
+        old_root: [u8; 32],
+        new_root: [u8; 32],
+    ) -> Result<()> {
+        withdraw_with_proof_handler(
+            ctx,
+            proof,
+            address_merkle_context,
+            output_merkle_tree_index,
+            amount,
+            link_hash,
+            nullifier,
+            vk_version,
+            proof_a,
+            proof_b,
+            proof_c,
+            old_root,
+            new_root,
+        )
     }
 
     pub fn create<'info>(
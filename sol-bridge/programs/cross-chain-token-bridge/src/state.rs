@@ -1,13 +1,44 @@
+use light_hasher::{to_byte_array::ToByteArray, Hasher, HasherError, Keccak};
 use light_sdk::{LightDiscriminator, LightHasher};
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, solana_program::hash::hash};
+use groth16_solana::groth16::Groth16Verifyingkey;
+
+use crate::error::ErrorCode;
 
 #[account]
 #[derive(InitSpace)]
 pub struct BridgeState {
-    // [43312, ...]
-    // pub valid_dest_chain_ids: Vec<u32>,
+    pub admin: Pubkey,
+    // set by propose_admin, cleared once the proposed admin accepts
+    pub pending_admin: Option<Pubkey>,
+    // halts deposits and withdrawals on every link
+    pub paused: bool,
+    // reports deposit status changes, set by the admin
+    pub relayer: Pubkey,
+    // root of the nullifier indexed merkle tree, advanced by every withdraw
+    pub nullifier_root: [u8; 32],
+    // destination chains are registered as ChainConfig PDAs
+}
+
+// Bridge-wide totals, aggregated from the per-link counters by
+// sync_bridge_stats so that no transfer has to write a global account.
+#[account]
+#[derive(InitSpace)]
+pub struct BridgeStats {
     pub deposit_count: u128,
     pub withdraw_count: u128,
+    pub last_synced_at: i64,
+}
+
+// Deposit sequence of one user across all links, seeded by
+// [b"deposit_nonce", owner]. The nonce is the deposit id, so (owner, deposit_id)
+// is unique and so is the nullifier hashed from it. Deposit record addresses
+// are derived from (owner, nonce) so clients know them before sending.
+#[account]
+#[derive(InitSpace)]
+pub struct DepositNonce {
+    pub owner: Pubkey,
+    pub nonce: u64,
 }
 
 #[account]
@@ -19,8 +50,274 @@ pub struct TokenBridge {
     pub dest_chain: u32,
     #[max_len(50)]
     pub dest_chain_mint_addr: String,
-    // #[max_len(64)]
-    // pub link_hash: String,
+    // decimals of the mint on each side, records carry amounts with
+    // min(source_decimals, dest_decimals) decimals
+    pub source_decimals: u8,
+    pub dest_decimals: u8,
+    pub mode: TokenBridgeMode,
+    // halts deposits and withdrawals on this link only
+    pub paused: bool,
+    // per-transfer and per-window caps, 0 = unlimited
+    pub max_transfer_amount: u64,
+    pub window_duration: i64,
+    pub max_deposit_volume: u64,
+    pub max_withdraw_volume: u64,
+    pub deposit_window: VolumeWindow,
+    pub withdraw_window: VolumeWindow,
+    // fee = fee_fixed + amount * fee_bps / 10_000, charged on deposit and withdraw
+    pub fee_bps: u16,
+    pub fee_fixed: u64,
+    // part of each withdraw fee owed to the relayer that submitted it, in bps of the fee
+    pub relayer_fee_share_bps: u16,
+    // part of each fee left in the vault for liquidity providers, in bps of the fee,
+    // LockRelease links only
+    pub lp_fee_share_bps: u16,
+    // protocol fees held in the fee vault, claimable by the admin
+    pub protocol_fees_accrued: u64,
+    // seconds after which an unprocessed deposit can be refunded, 0 = no refunds
+    pub refund_timeout: i64,
+    // LockRelease accounting against the vault shared with the reverse link,
+    // vault balance = sum over both directions of locked + liquidity_provided - released
+    pub total_locked: u64,
+    pub total_released: u64,
+    pub total_liquidity_provided: u64,
+    // deposits made on this link, and how many of them BridgeStats has counted
+    pub deposit_count: u64,
+    pub synced_deposit_count: u64,
+    // withdrawals paid out on this link, their ids are 1..=withdraw_count
+    pub withdraw_count: u64,
+    pub synced_withdraw_count: u64,
+    // PDA seed, derive_link_hash(source_chain, source_chain_mint_addr, dest_chain, dest_chain_mint_addr)
+    #[max_len(16)]
+    pub link_hash: String,
+}
+
+impl TokenBridge {
+    pub fn record_deposit(&mut self, now: i64, amount: u64) -> Result<()> {
+        self.check_transfer_amount(amount)?;
+        self.deposit_window.record(
+            now,
+            self.window_duration,
+            self.max_deposit_volume,
+            amount,
+            ErrorCode::DepositVolumeLimitExceeded,
+        )
+    }
+
+    pub fn record_withdraw(&mut self, now: i64, amount: u64) -> Result<()> {
+        self.check_transfer_amount(amount)?;
+        self.withdraw_window.record(
+            now,
+            self.window_duration,
+            self.max_withdraw_volume,
+            amount,
+            ErrorCode::WithdrawVolumeLimitExceeded,
+        )
+    }
+
+    pub fn next_withdrawal_id(&mut self) -> Result<u64> {
+        self.withdraw_count = self.withdraw_count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        Ok(self.withdraw_count)
+    }
+
+    pub fn fee_for(&self, amount: u64) -> Result<u64> {
+        let variable_fee = amount as u128 * self.fee_bps as u128 / BPS_DENOMINATOR as u128;
+        let fee = (variable_fee as u64).checked_add(self.fee_fixed).ok_or(ErrorCode::MathOverflow)?;
+        require!(fee < amount, ErrorCode::FeeExceedsAmount);
+        Ok(fee)
+    }
+
+    pub fn relayer_share_of(&self, fee: u64) -> u64 {
+        (fee as u128 * self.relayer_fee_share_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+
+    pub fn lp_share_of(&self, fee: u64) -> u64 {
+        if self.mode != TokenBridgeMode::LockRelease {
+            return 0;
+        }
+        (fee as u128 * self.lp_fee_share_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+
+    // The hash of the link that leaves solana between this link's two mints.
+    // Vaults, pools and LP mints are seeded by [b"vault", vault_link_hash] so
+    // both directions of one mint pair share collateral, and no link to another
+    // chain or remote mint can touch it.
+    pub fn vault_link_hash(&self) -> String {
+        if self.source_chain == SOURCE_CHAIN_ID {
+            self.link_hash.clone()
+        } else {
+            self.reverse_link_hash()
+        }
+    }
+
+    pub fn add_locked(&mut self, amount: u64) -> Result<()> {
+        self.total_locked = self.total_locked.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn add_released(&mut self, amount: u64) -> Result<()> {
+        self.total_released = self.total_released.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    // locked + liquidity_provided - released. Negative on a link whose
+    // withdraws are paid from what the reverse link locked.
+    pub fn ledger_balance(&self) -> i128 {
+        self.total_locked as i128 + self.total_liquidity_provided as i128 - self.total_released as i128
+    }
+
+    // the link in the other direction between the same two mints
+    pub fn reverse_link_hash(&self) -> String {
+        derive_link_hash(self.dest_chain, &self.dest_chain_mint_addr, self.source_chain, &self.source_chain_mint_addr)
+    }
+
+    pub fn solana_mint_addr(&self) -> Option<&String> {
+        if self.source_chain == SOURCE_CHAIN_ID {
+            Some(&self.source_chain_mint_addr)
+        } else if self.dest_chain == SOURCE_CHAIN_ID {
+            Some(&self.dest_chain_mint_addr)
+        } else {
+            None
+        }
+    }
+
+    // vaults, pools and fee vaults are seeded by the mint passed in, it has to
+    // be this link's solana mint
+    pub fn check_solana_mint(&self, mint: &Pubkey) -> Result<()> {
+        let solana_mint_addr = self.solana_mint_addr().ok_or(ErrorCode::MintMismatch)?;
+        require!(mint.to_string() == *solana_mint_addr, ErrorCode::MintMismatch);
+        Ok(())
+    }
+
+    pub fn canonical_decimals(&self) -> u8 {
+        self.source_decimals.min(self.dest_decimals)
+    }
+
+    // Converts an amount with `decimals` decimals into the canonical bridge
+    // unit, rounding down. Returns the canonical amount and the dust left over,
+    // in the original unit. Takes a u128 so 18-decimal EVM amounts fit.
+    pub fn normalise_amount(&self, amount: u128, decimals: u8) -> Result<(u64, u128)> {
+        let factor = self.scale_factor(decimals)?;
+        let canonical = u64::try_from(amount / factor).map_err(|_| ErrorCode::MathOverflow)?;
+        Ok((canonical, amount % factor))
+    }
+
+    pub fn denormalise_amount(&self, amount: u64, decimals: u8) -> Result<u64> {
+        let local = (amount as u128).checked_mul(self.scale_factor(decimals)?).ok_or(ErrorCode::MathOverflow)?;
+        u64::try_from(local).map_err(|_| ErrorCode::MathOverflow.into())
+    }
+
+    fn scale_factor(&self, decimals: u8) -> Result<u128> {
+        let shift = decimals.checked_sub(self.canonical_decimals()).ok_or(ErrorCode::MintDecimalsMismatch)?;
+        10u128.checked_pow(shift as u32).ok_or(ErrorCode::MathOverflow.into())
+    }
+
+    fn check_transfer_amount(&self, amount: u64) -> Result<()> {
+        require!(
+            self.max_transfer_amount == 0 || amount <= self.max_transfer_amount,
+            ErrorCode::TransferAmountExceedsLimit
+        );
+        Ok(())
+    }
+}
+
+// Link PDAs are seeded by [b"tb", link_hash], where clients derive link_hash
+// as the first 16 hex characters of sha256("{source}_{source_mint}_{dest}_{dest_mint}").
+pub fn derive_link_hash(source_chain: u32, source_mint: &str, dest_chain: u32, dest_mint: &str) -> String {
+    let link = format!("{}_{}_{}_{}", source_chain, source_mint, dest_chain, dest_mint);
+    let digest = hash(link.as_bytes()).to_bytes();
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum TokenBridgeMode {
+    // Solana-native mints: deposits lock tokens in the vault, withdraws release
+    // them. The optional token_vault and liquidity_pool accounts of deposit,
+    // withdraw and refund_deposit are only passed for these links.
+    LockRelease,
+    // mints owned by the program's [b"mint_authority"] PDA: deposits burn,
+    // withdraws mint
+    MintBurn,
+}
+
+// Sliding window approximated from the current and the previous fixed window:
+// the previous window's volume is weighted by how much of it still overlaps
+// the last `window_duration` seconds.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, InitSpace)]
+pub struct VolumeWindow {
+    pub window_start: i64,
+    pub current_volume: u64,
+    pub previous_volume: u64,
+}
+
+impl VolumeWindow {
+    pub fn record(
+        &mut self,
+        now: i64,
+        window_duration: i64,
+        limit: u64,
+        amount: u64,
+        exceeded: ErrorCode,
+    ) -> Result<()> {
+        if limit == 0 || window_duration <= 0 {
+            return Ok(());
+        }
+
+        let aligned_start = now - now.rem_euclid(window_duration);
+        if aligned_start == self.window_start + window_duration {
+            self.previous_volume = self.current_volume;
+            self.current_volume = 0;
+            self.window_start = aligned_start;
+        } else if aligned_start != self.window_start {
+            self.previous_volume = 0;
+            self.current_volume = 0;
+            self.window_start = aligned_start;
+        }
+
+        let remaining = (window_duration - (now - aligned_start)) as u128;
+        let carried = self.previous_volume as u128 * remaining / window_duration as u128;
+        let volume = carried + self.current_volume as u128 + amount as u128;
+        if volume > limit as u128 {
+            return Err(exceeded.into());
+        }
+
+        self.current_volume = self.current_volume.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DepositStatus {
+    // waiting for the relayer
+    #[default]
+    Pending,
+    // returned to the owner by refund_deposit, must not be released on the destination chain
+    Refunded,
+    // release submitted on the destination chain, dest_tx_hash is set. Back to
+    // Pending through reset_relayed_deposit if the release is cancelled there
+    Relayed,
+    // release confirmed on the destination chain
+    Finalized,
+}
+
+impl DepositStatus {
+    pub fn can_move_to(&self, next: DepositStatus) -> bool {
+        matches!(
+            (self, next),
+            (DepositStatus::Pending, DepositStatus::Relayed)
+                | (DepositStatus::Pending, DepositStatus::Finalized)
+                | (DepositStatus::Relayed, DepositStatus::Finalized)
+        )
+    }
+}
+
+impl ToByteArray for DepositStatus {
+    const NUM_FIELDS: usize = 1;
+    const IS_PRIMITIVE: bool = true;
+
+    fn to_byte_array(&self) -> std::result::Result<[u8; 32], HasherError> {
+        (*self as u8).to_byte_array()
+    }
 }
 
 #[event] // to include in anchor idl
@@ -33,22 +330,32 @@ pub struct DepositRecordCompressedAccount {
     pub owner: Pubkey,
     pub source_chain_id: u32,
     pub dest_chain_id: u32,
-    // eth addr - 0x(40 chars) - hex string
-    // #[max_len(42)]
-    pub dest_chain_addr: String,
+    // raw recipient address, left-padded to 32 bytes (an evm address fills the last 20)
+    #[hash]
+    pub dest_chain_addr: [u8; 32],
     pub dest_chain_mint_addr: String,
     #[hash]
     pub mint: Pubkey,
+    // credited on the destination chain, after the fee, in the canonical bridge unit
     pub amount: u64,
+    // in the mint's unit
+    pub fee: u64,
     pub timestamp: i64,
+    // the owner's deposit nonce, counted across all links
     #[hash]
     pub deposit_id: u128,
+    pub status: DepositStatus,
+    // release transaction on the destination chain, zero until relayed
+    #[hash]
+    pub dest_tx_hash: [u8; 32],
 }
 
 #[event]
 #[derive(
     Clone, Debug, Default, LightDiscriminator, LightHasher
 )]
+// field names mirror the EVM side's withdrawal struct
+#[allow(non_snake_case)]
 pub struct WithdrawalRecordCompressedAccount {
     pub depositer: String,
     pub sourceChainId: u64,
@@ -58,19 +365,366 @@ pub struct WithdrawalRecordCompressedAccount {
     #[hash]
     pub destChainMintAddr: Pubkey,
     pub tokenMint: String,
+    // proven amount in the canonical bridge unit
     pub amount: u64,
+    // in the mint's unit, deducted from the rescaled amount
+    pub fee: u64,
     pub timestamp: i64,
+    // dense per link, see TokenBridge::next_withdrawal_id
     pub withdrawalId: u128,
 }
 
 pub const SOURCE_CHAIN_ID: u32 = 1u32;
+pub const BPS_DENOMINATOR: u16 = 10_000;
+// carried by every event, bumped when an event's layout changes
+pub const EVENT_VERSION: u8 = 1;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum AddressFormat {
+    // 20-byte account addresses
+    Evm,
+    // 32-byte ed25519 public keys
+    Solana,
+}
+
+// Recipient on the destination chain. The variant has to match the
+// destination's ChainConfig.address_format.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum DestinationAddress {
+    // checksummed: the EIP-55 form of address, "0x" optional. Checked when given.
+    Evm { address: [u8; 20], checksummed: Option<String> },
+    Solana { address: [u8; 32] },
+}
+
+impl DestinationAddress {
+    pub fn validate(&self, address_format: AddressFormat) -> Result<()> {
+        match (self, address_format) {
+            (DestinationAddress::Evm { address, checksummed }, AddressFormat::Evm) => {
+                require!(*address != [0u8; 20], ErrorCode::InvalidDestinationAddress);
+                if let Some(checksummed) = checksummed {
+                    let checksummed = checksummed.strip_prefix("0x").unwrap_or(checksummed);
+                    require!(*checksummed == eip55_checksum(address)?, ErrorCode::InvalidAddressChecksum);
+                }
+                Ok(())
+            }
+            (DestinationAddress::Solana { address }, AddressFormat::Solana) => {
+                require!(*address != [0u8; 32], ErrorCode::InvalidDestinationAddress);
+                Ok(())
+            }
+            _ => err!(ErrorCode::InvalidDestinationAddress),
+        }
+    }
+
+    // the raw address bytes, left-padded to 32, as stored in deposit records
+    pub fn to_record_bytes(&self) -> [u8; 32] {
+        match self {
+            DestinationAddress::Evm { address, .. } => {
+                let mut bytes = [0u8; 32];
+                bytes[12..].copy_from_slice(address);
+                bytes
+            }
+            DestinationAddress::Solana { address } => *address,
+        }
+    }
+}
 
+// Hex of address without "0x", letters uppercased where the matching nibble
+// of keccak256(lowercase hex) is 8 or more.
+fn eip55_checksum(address: &[u8; 20]) -> Result<String> {
+    let lower: String = address.iter().map(|b| format!("{:02x}", b)).collect();
+    let digest = Keccak::hash(lower.as_bytes()).map_err(|_| ErrorCode::InvalidAddressChecksum)?;
+    Ok(lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (digest[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+            if nibble >= 8 { c.to_ascii_uppercase() } else { c }
+        })
+        .collect())
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum VerifierType {
+    // transfers from this chain are proven with a groth16 proof of the given circuit
+    Groth16 { circuit_id: u32 },
+    // transfers from this chain are attested by the relayer
+    Relayer,
+}
+
+// Registry entry for a chain the bridge talks to, seeded by
+// [b"chain_config", chain_id]. Links can only be created to, deposits only
+// made to, and withdrawals only accepted from chains that are registered and
+// enabled.
+#[account]
+#[derive(InitSpace)]
+pub struct ChainConfig {
+    pub chain_id: u32,
+    pub enabled: bool,
+    pub address_format: AddressFormat,
+    // blocks the relayer waits for on this chain before acting on an event
+    pub confirmations: u32,
+    pub verifier: VerifierType,
+}
+
+impl ChainConfig {
+    // circuit the withdrawal proofs from this chain are verified against
+    pub fn groth16_circuit_id(&self) -> Result<u32> {
+        match self.verifier {
+            VerifierType::Groth16 { circuit_id } => Ok(circuit_id),
+            VerifierType::Relayer => err!(ErrorCode::UnsupportedVerifier),
+        }
+    }
+}
+
+// Liquidity provider accounting for one vault, seeded like it by
+// [b"pool", vault_link_hash]. Providers hold shares minted from the
+// [b"lp_mint", vault_link_hash] PDA mint, priced against total_liquidity.
+#[account]
+#[derive(InitSpace)]
+pub struct LiquidityPool {
+    pub mint: Pubkey,
+    #[max_len(16)]
+    pub link_hash: String,
+    // tokens owed to share holders: provided liquidity plus their fee share
+    pub total_liquidity: u64,
+    // depositor collateral in the vault that can still be refunded or paid
+    // out, providers can only withdraw what is above it
+    pub reserved: u64,
+}
+
+impl LiquidityPool {
+    pub fn shares_for(&self, amount: u64, share_supply: u64) -> Result<u64> {
+        if share_supply == 0 || self.total_liquidity == 0 {
+            return Ok(amount);
+        }
+        let shares = amount as u128 * share_supply as u128 / self.total_liquidity as u128;
+        u64::try_from(shares).map_err(|_| ErrorCode::MathOverflow.into())
+    }
+
+    pub fn amount_for(&self, shares: u64, share_supply: u64) -> Result<u64> {
+        require!(share_supply > 0, ErrorCode::InvalidArgs);
+        let amount = shares as u128 * self.total_liquidity as u128 / share_supply as u128;
+        u64::try_from(amount).map_err(|_| ErrorCode::MathOverflow.into())
+    }
+
+    pub fn available(&self, vault_balance: u64) -> u64 {
+        vault_balance.saturating_sub(self.reserved)
+    }
+
+    pub fn add_liquidity(&mut self, amount: u64) -> Result<()> {
+        self.total_liquidity = self.total_liquidity.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn reserve(&mut self, amount: u64) -> Result<()> {
+        self.reserved = self.reserved.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    // payouts use depositor collateral first, then provider liquidity
+    pub fn pay_out(&mut self, amount: u64) -> Result<()> {
+        let from_reserved = amount.min(self.reserved);
+        self.reserved -= from_reserved;
+        self.total_liquidity = self.total_liquidity
+            .checked_sub(amount - from_reserved)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
+// Withdraw fees owed to one relayer for one mint, held in the mint's fee vault.
+#[account]
+#[derive(InitSpace)]
+pub struct RelayerBalance {
+    pub relayer: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+// Groth16 proof for one withdrawal, seeded by [b"withdrawal_proof", creator, nullifier]
+// so nobody else can create or overwrite it. Only its creator can use it in
+// withdraw, which closes it.
 #[account]
 #[derive(InitSpace)]
 pub struct WithdrawalProof {
+    pub creator: Pubkey,
+    pub created_at: i64,
     pub proof_a: [u8; 64],
     pub proof_b: [u8; 128],
     pub proof_c: [u8; 64],
     pub nullifier: [u8; 32],
+    pub old_root: [u8; 32],
     pub new_root: [u8; 32],
-}
\ No newline at end of file
+}
+
+// seconds after which the creator of an unused WithdrawalProof can close it
+pub const WITHDRAWAL_PROOF_EXPIRY: i64 = 24 * 60 * 60;
+
+pub const MAX_VK_PUBLIC_INPUTS: usize = 16;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum VerifyingKeyStatus {
+    // uploaded, vk_ic may still be incomplete
+    Registered,
+    // accepted by instructions that verify proofs for circuit_id
+    Active,
+    // permanently rejected
+    Retired,
+}
+
+// Groth16 verifying key for one version of a circuit, seeded by
+// [b"vk", circuit_id, version]. Several versions of a circuit can be
+// active at once while provers migrate to a new trusted setup.
+#[account]
+#[derive(InitSpace)]
+pub struct VerifyingKey {
+    pub circuit_id: u32,
+    pub version: u32,
+    pub status: VerifyingKeyStatus,
+    pub nr_public_inputs: u8,
+    pub vk_alpha_g1: [u8; 64],
+    pub vk_beta_g2: [u8; 128],
+    pub vk_gamma_g2: [u8; 128],
+    pub vk_delta_g2: [u8; 128],
+    #[max_len(MAX_VK_PUBLIC_INPUTS + 1)]
+    pub vk_ic: Vec<[u8; 64]>,
+}
+
+impl VerifyingKey {
+    pub fn is_complete(&self) -> bool {
+        self.vk_ic.len() == self.nr_public_inputs as usize + 1
+    }
+
+    pub fn to_groth16(&self) -> Groth16Verifyingkey<'_> {
+        Groth16Verifyingkey {
+            nr_pubinputs: self.vk_ic.len(),
+            vk_alpha_g1: self.vk_alpha_g1,
+            vk_beta_g2: self.vk_beta_g2,
+            vk_gamme_g2: self.vk_gamma_g2,
+            vk_delta_g2: self.vk_delta_g2,
+            vk_ic: &self.vk_ic,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_token_bridge() -> TokenBridge {
+        let data = vec![0u8; 8 + TokenBridge::INIT_SPACE];
+        TokenBridge::try_deserialize_unchecked(&mut &data[..]).unwrap()
+    }
+
+    #[test]
+    fn evm_destination_checksum_follows_eip55() {
+        // checksummed example from EIP-55
+        let mut address = [0u8; 20];
+        for (i, byte) in address.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&"5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"[i * 2..i * 2 + 2], 16).unwrap();
+        }
+        let checksummed = |s: &str| DestinationAddress::Evm { address, checksummed: Some(s.to_string()) };
+
+        assert!(checksummed("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").validate(AddressFormat::Evm).is_ok());
+        assert!(checksummed("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").validate(AddressFormat::Evm).is_ok());
+        assert!(checksummed("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").validate(AddressFormat::Evm).is_err());
+        assert!(DestinationAddress::Evm { address, checksummed: None }.validate(AddressFormat::Evm).is_ok());
+        assert!(DestinationAddress::Evm { address, checksummed: None }.validate(AddressFormat::Solana).is_err());
+    }
+
+    #[test]
+    fn link_hash_matches_client_derivation() {
+        // sha256("1_So11111111111111111111111111111111111111112_31337_0x610178dA211FEF7D417bC0e6FeD39F05609AD788")[..16]
+        assert_eq!(
+            derive_link_hash(1, "So11111111111111111111111111111111111111112", 31337, "0x610178dA211FEF7D417bC0e6FeD39F05609AD788"),
+            "36d10e255a69e7d3"
+        );
+    }
+
+    #[test]
+    fn both_directions_of_a_mint_pair_share_a_vault() {
+        let mut outbound = empty_token_bridge();
+        outbound.source_chain = SOURCE_CHAIN_ID;
+        outbound.source_chain_mint_addr = "So11111111111111111111111111111111111111112".to_string();
+        outbound.dest_chain = 31337;
+        outbound.dest_chain_mint_addr = "0x610178dA211FEF7D417bC0e6FeD39F05609AD788".to_string();
+        outbound.link_hash = derive_link_hash(
+            outbound.source_chain, &outbound.source_chain_mint_addr, outbound.dest_chain, &outbound.dest_chain_mint_addr,
+        );
+
+        let mut inbound = empty_token_bridge();
+        inbound.source_chain = outbound.dest_chain;
+        inbound.source_chain_mint_addr = outbound.dest_chain_mint_addr.clone();
+        inbound.dest_chain = SOURCE_CHAIN_ID;
+        inbound.dest_chain_mint_addr = outbound.source_chain_mint_addr.clone();
+        inbound.link_hash = outbound.reverse_link_hash();
+
+        assert_eq!(outbound.vault_link_hash(), outbound.link_hash);
+        assert_eq!(inbound.vault_link_hash(), outbound.link_hash);
+
+        // a link to another remote mint on the same chain gets its own vault
+        let mut other = outbound.clone();
+        other.dest_chain_mint_addr = "0x0000000000000000000000000000000000000001".to_string();
+        other.link_hash = derive_link_hash(
+            other.source_chain, &other.source_chain_mint_addr, other.dest_chain, &other.dest_chain_mint_addr,
+        );
+        assert_ne!(other.vault_link_hash(), outbound.vault_link_hash());
+    }
+
+    #[test]
+    fn evm_amounts_normalise_to_the_canonical_unit() {
+        let mut link = empty_token_bridge();
+        link.source_decimals = 18;
+        link.dest_decimals = 2;
+
+        // 100 tokens at 18 decimals is past u64::MAX
+        let amount = 100u128 * 10u128.pow(18) + 7;
+        let (canonical, dust) = link.normalise_amount(amount, 18).unwrap();
+        assert_eq!(canonical, 100 * 100);
+        assert_eq!(dust, 7);
+        assert_eq!(link.denormalise_amount(canonical, 2).unwrap(), 100 * 100);
+        assert!(link.denormalise_amount(canonical, 18).is_err());
+    }
+
+    #[test]
+    fn only_groth16_chains_name_a_circuit() {
+        let mut config = ChainConfig {
+            chain_id: 31337,
+            enabled: true,
+            address_format: AddressFormat::Evm,
+            confirmations: 12,
+            verifier: VerifierType::Groth16 { circuit_id: 1 },
+        };
+        assert_eq!(config.groth16_circuit_id().unwrap(), 1);
+
+        config.verifier = VerifierType::Relayer;
+        assert!(config.groth16_circuit_id().is_err());
+    }
+
+    #[test]
+    fn payouts_past_the_collateral_come_out_of_provider_liquidity() {
+        let mut pool = LiquidityPool { mint: Pubkey::default(), link_hash: String::new(), total_liquidity: 500, reserved: 100 };
+
+        pool.pay_out(60).unwrap();
+        assert_eq!((pool.reserved, pool.total_liquidity), (40, 500));
+
+        pool.pay_out(140).unwrap();
+        assert_eq!((pool.reserved, pool.total_liquidity), (0, 400));
+
+        assert!(pool.pay_out(401).is_err());
+    }
+
+    #[test]
+    fn withdrawal_ids_are_contiguous_per_link() {
+        let mut link = empty_token_bridge();
+        let mut other_link = empty_token_bridge();
+
+        let ids: Vec<u64> = (0..5).map(|_| link.next_withdrawal_id().unwrap()).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+        assert_eq!(link.withdraw_count, 5);
+
+        // another link's withdrawals don't take ids from this one
+        assert_eq!(other_link.next_withdrawal_id().unwrap(), 1);
+        assert_eq!(link.next_withdrawal_id().unwrap(), 6);
+    }
+}
@@ -1,46 +0,0 @@
-use groth16_solana::groth16::Groth16Verifyingkey;
-
-pub const ETHDEPOSIT_VERIFYINGKEY: Groth16Verifyingkey =  Groth16Verifyingkey {
-	nr_pubinputs: 3,
-
-	vk_alpha_g1: [
-		43,109,70,80,164,169,152,77,210,85,162,84,113,124,233,133,212,162,98,82,41,250,99,186,18,104,240,200,249,122,249,235,
-		34,243,153,29,80,183,147,163,102,15,166,173,140,225,86,172,62,180,120,183,126,139,11,116,255,223,75,230,84,197,97,13,
-	],
-
-	vk_beta_g2: [
-		14,39,210,67,134,0,180,17,51,56,91,72,252,36,37,86,232,27,68,171,77,14,233,163,55,109,167,49,88,118,194,186,
-		7,79,131,116,201,221,174,144,3,47,185,147,8,175,128,224,135,2,125,172,62,200,242,3,189,126,232,13,139,84,187,68,
-		33,131,116,253,184,180,29,43,54,205,248,181,12,154,146,86,49,56,31,80,250,107,154,213,210,129,161,114,173,33,41,49,
-		11,4,182,38,156,27,7,66,79,106,105,133,223,171,12,239,215,4,163,92,174,146,225,80,72,245,51,244,197,218,65,43,
-	],
-
-	vk_gamme_g2: [
-		25,142,147,147,146,13,72,58,114,96,191,183,49,251,93,37,241,170,73,51,53,169,231,18,151,228,133,183,174,243,18,194,
-		24,0,222,239,18,31,30,118,66,106,0,102,94,92,68,121,103,67,34,212,247,94,218,221,70,222,189,92,217,146,246,237,
-		9,6,137,208,88,95,240,117,236,158,153,173,105,12,51,149,188,75,49,51,112,179,142,243,85,172,218,220,209,34,151,91,
-		18,200,94,165,219,140,109,235,74,171,113,128,141,203,64,143,227,209,231,105,12,67,211,123,76,230,204,1,102,250,125,170,
-	],
-
-	vk_delta_g2: [
-		25,142,147,147,146,13,72,58,114,96,191,183,49,251,93,37,241,170,73,51,53,169,231,18,151,228,133,183,174,243,18,194,
-		24,0,222,239,18,31,30,118,66,106,0,102,94,92,68,121,103,67,34,212,247,94,218,221,70,222,189,92,217,146,246,237,
-		9,6,137,208,88,95,240,117,236,158,153,173,105,12,51,149,188,75,49,51,112,179,142,243,85,172,218,220,209,34,151,91,
-		18,200,94,165,219,140,109,235,74,171,113,128,141,203,64,143,227,209,231,105,12,67,211,123,76,230,204,1,102,250,125,170,
-	],
-
-	vk_ic: &[
-		[
-			25,85,98,215,211,224,211,123,184,29,159,227,253,15,57,167,140,163,84,190,123,203,89,194,97,9,148,249,152,240,127,224,
-			40,248,53,87,51,218,220,249,47,205,60,222,234,231,19,144,89,15,18,166,73,148,34,63,214,146,221,169,15,224,191,130,
-		],
-		[
-			21,20,66,153,138,144,18,75,136,19,57,230,185,70,126,75,17,100,171,243,187,18,23,198,169,231,234,232,25,98,174,223,
-			46,152,42,98,84,181,189,149,141,128,241,44,18,26,80,84,21,125,18,15,203,92,185,177,143,253,205,131,254,26,189,45,
-		],
-		[
-			1,2,118,81,109,229,55,243,178,250,116,72,71,113,149,194,50,221,50,152,65,254,48,105,162,251,79,215,202,31,144,72,
-			39,187,246,147,63,240,15,91,230,106,255,56,199,202,223,85,94,43,186,32,7,113,235,106,12,10,162,249,154,128,98,12,
-		],
-	]
-};
\ No newline at end of file
@@ -0,0 +1,7 @@
+// One `<circuit>_fixture_verifies` test per circom/<circuit>_js, generated by
+// build.rs from the verification key and the proof.json / public.json next to it.
+use groth16_solana::groth16::Groth16Verifyingkey;
+
+use crate::zk::groth16_verifier;
+
+include!(concat!(env!("OUT_DIR"), "/verifying_key_fixtures.rs"));
@@ -1,4 +1,6 @@
 pub mod verifier;
 pub use verifier::*;
-pub mod eth_deposit_verifying_key;
-pub use eth_deposit_verifying_key::*;
\ No newline at end of file
+pub mod public_inputs;
+pub use public_inputs::*;
+#[cfg(test)]
+mod fixtures;
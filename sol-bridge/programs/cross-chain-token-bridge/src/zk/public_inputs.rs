@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+// BN254 scalar field modulus, big-endian
+const BN254_SCALAR_FIELD: [u8; 32] = [
+    48,100,78,114,225,49,160,41,184,80,69,182,129,129,88,93,
+    40,51,232,72,121,185,112,145,67,225,245,147,240,0,0,1,
+];
+
+pub const WITHDRAWAL_PUBLIC_INPUTS: usize = 8;
+
+// Same reduction the circom witness calculator applies to its inputs, so a
+// 32-byte pubkey maps to the field element the prover committed to.
+pub fn bytes_to_field(bytes: [u8; 32]) -> [u8; 32] {
+    let mut value = bytes;
+    while value >= BN254_SCALAR_FIELD {
+        let mut borrow = 0u16;
+        for i in (0..32).rev() {
+            let rhs = BN254_SCALAR_FIELD[i] as u16 + borrow;
+            let lhs = value[i] as u16;
+            if lhs >= rhs {
+                value[i] = (lhs - rhs) as u8;
+                borrow = 0;
+            } else {
+                value[i] = (lhs + 256 - rhs) as u8;
+                borrow = 1;
+            }
+        }
+    }
+    value
+}
+
+pub fn u128_to_field(value: u128) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes[16..].copy_from_slice(&value.to_be_bytes());
+    bytes
+}
+
+// Token bridges store foreign mints as the base58 of their raw bytes; the
+// circuit takes the same bytes as a big-endian number.
+pub fn mint_addr_to_field(mint_addr: &str) -> Result<[u8; 32]> {
+    let bytes = bs58::decode(mint_addr).into_vec().map_err(|_| error!(ErrorCode::InvalidArgs))?;
+    require!(bytes.len() <= 32, ErrorCode::InvalidArgs);
+    let mut value = [0u8; 32];
+    value[32 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(bytes_to_field(value))
+}
+
+// Public signal order of circom/ethDepositProof.circom: outputs first
+// (nullifier, new_root), then the public inputs in declaration order.
+#[allow(clippy::too_many_arguments)]
+pub fn withdrawal_public_inputs(
+    nullifier: [u8; 32],
+    new_root: [u8; 32],
+    source_chain: u32,
+    recipient: &Pubkey,
+    mint: &Pubkey,
+    source_mint: [u8; 32],
+    amount: u128,
+    old_root: [u8; 32],
+) -> [[u8; 32]; WITHDRAWAL_PUBLIC_INPUTS] {
+    [
+        nullifier,
+        new_root,
+        u128_to_field(source_chain as u128),
+        bytes_to_field(recipient.to_bytes()),
+        bytes_to_field(mint.to_bytes()),
+        source_mint,
+        u128_to_field(amount),
+        old_root,
+    ]
+}
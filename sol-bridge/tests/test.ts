@@ -19,6 +19,12 @@ import {
   sleep,
 } from "@lightprotocol/stateless.js";
 import bs58 from "bs58";
+import { expect } from "chai";
+import { IndexedMerkleTree } from "@jayanth-kumar-morem/indexed-merkle-tree";
+import { poseidon9 } from "poseidon-lite";
+// @ts-ignore
+import * as snarkjs from "snarkjs";
+import { deriveLinkHash, deriveVaultLinkHash, fieldToBytes, g1ToBytes, g2ToBytes } from "../lib/bridge";
 
 const path = require("path");
 const os = require("os");
@@ -27,11 +33,38 @@ require("dotenv").config();
 const anchorWalletPath = path.join(os.homedir(), ".config/solana/id.json");
 process.env.ANCHOR_WALLET = anchorWalletPath;
 
-const withdrawalNullifier = [11,3,119,82,135,205,250,45,160,213,133,169,79,212,130,204,137,128,91,19,82,142,63,56,50,224,60,189,43,8,50,4];
+// root of the relayer's nullifier tree when the bridge is initialised: the depth-32
+// indexed merkle tree holding only its (0, 0) sentinel leaf
+const initialNullifierRoot = fieldToBytes("20904263309775236245566347617139780413277211320889904019085937429497372345062");
+
+// circuit id the eth deposit keys are registered under and EVM chain configs verify with
+const ETH_DEPOSIT_CIRCUIT_ID = 1;
+
+// version of the eth deposit circuit's verifying key registered for this run
+const withdrawalVkVersion = 1;
+
+// version register_verifying_key must refuse a key that accepts any proof under
+const ACCEPT_ANY_PROOF_VK_VERSION = 99;
 
 describe("test-anchor", () => {
   const program = anchor.workspace.CrossChainTokenBridge as Program<CrossChainTokenBridge>;
 
+  // set up by the first test and reused by the ones that need a live link
+  let scenario: {
+    rpc: Rpc;
+    signer: web3.Keypair;
+    mint: PublicKey;
+    addressTree: PublicKey;
+    addressQueue: PublicKey;
+    outputMerkleTree: PublicKey;
+    destChainId: number;
+    destChainMintAddr: string;
+    // the Pending deposit made on the solana -> destChainId link during setup
+    depositRecordAddress: PublicKey;
+    // the relayer's view of the nullifier tree, mirrored by the program's nullifier root
+    nullifierTree: IndexedMerkleTree;
+  };
+
   it("", async () => {
     let signer = new web3.Keypair();
     let rpc = createRpc(
@@ -57,6 +90,7 @@ describe("test-anchor", () => {
     );
     const address = deriveAddress(seed, addressTree);
     await initInstructionCall(rpc, program,signer);
+    await registerVerifyingKeyCall(rpc, program, signer, withdrawalVkVersion);
 
     const conn = new Connection("http://localhost:8899", "confirmed");
     const decimals = 2;
@@ -66,56 +100,54 @@ describe("test-anchor", () => {
     const mintSig = await mintTo(
       conn, signer, mint, ata.address,signer, amount
     );
-    await depositToTokenVault(rpc, signer, program, mint);
 
-    // BridgeState PDA to read current deposit_count
-    const bridgeStatePda = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("bridge_state")],
+    const dest_chain_id = 31337; // from hardhat config
+    const destChainAddrHex = process.env.DEST_CHAIN_ADDR || "8626f6940E2eb28930eFb4CeF49B2d1F2C9C1199"; // no 0x prefix hex
+    // mixed case means EIP-55 checksummed, which the program then verifies
+    const isChecksummed = /[a-f]/.test(destChainAddrHex) && /[A-F]/.test(destChainAddrHex);
+    const dest_chain_addr = {
+      evm: {
+        address: Array.from(Buffer.from(destChainAddrHex, "hex")),
+        checksummed: isChecksummed ? `0x${destChainAddrHex}` : null,
+      },
+    };
+    const dest_chain_mint_addr = bs58.encode(Buffer.from(
+      process.env.DEST_CHAIN_MINT_ADDR || "610178da211fef7d417bc0e6fed39f05609ad788",
+      "hex"
+    ));
+    const SOLANA_CHAIN_ID = 1;
+
+    // deposit records are addressed by (depositor, nonce); the nonce is the
+    // depositor's sequence across all links, 0 before the first deposit
+    const depositNoncePda = PublicKey.findProgramAddressSync(
+      [Buffer.from("deposit_nonce"), signer.publicKey.toBuffer()],
       program.programId,
     )[0];
-
-    // Fetch current deposit_count (0 if just initialised)
-    const bridgeStateAccount: any = await program.account.bridgeState.fetchNullable(bridgeStatePda);
-    const currentDepositCount = bridgeStateAccount ? BigInt(bridgeStateAccount.depositCount.toString()) : BigInt(0);
-    const nextDepositCount = currentDepositCount + BigInt(1);
-    // Convert the deposit count to a 16-byte buffer in little-endian format
-    // This is needed because the Solana program expects a u128 (128-bit) value
-    // represented as bytes for the deposit record seed derivation
-    const depositCountBytes = Buffer.alloc(16);
-    
-    // Split the 128-bit number into two 64-bit parts since JavaScript
-    // can only handle 64-bit integers natively
-    const twoPow64 = BigInt(1) << BigInt(64); // 2^64
-    const low = nextDepositCount % twoPow64;   // Lower 64 bits
-    const high = nextDepositCount / twoPow64;  // Upper 64 bits
-    
-    // Write both 64-bit parts to the buffer in little-endian format
-    // Bytes 0-7: lower 64 bits, Bytes 8-15: upper 64 bits
-    depositCountBytes.writeBigUInt64LE(low, 0);
-    depositCountBytes.writeBigUInt64LE(high, 8);
+    const depositNonceAccount = await program.account.depositNonce.fetchNullable(depositNoncePda);
+    const currentNonce = depositNonceAccount ? BigInt(depositNonceAccount.nonce.toString()) : BigInt(0);
+    const nonceBytes = Buffer.alloc(8);
+    nonceBytes.writeBigUInt64LE(currentNonce + BigInt(1));
 
     const depositRecordSeed = deriveAddressSeed(
       [
         new TextEncoder().encode("deposit"),
         signer.publicKey.toBytes(),
-        depositCountBytes,
+        nonceBytes,
       ],
       program.programId,
     );
     const depositRecordAddress = deriveAddress(depositRecordSeed, addressTree);
     console.log("depositRecordAddress", depositRecordAddress);
-    const dest_chain_id = 31337; // from hardhat config
-    const dest_chain_addr = bs58.encode(Buffer.from(
-      process.env.DEST_CHAIN_ADDR || "8626f6940E2eb28930eFb4CeF49B2d1F2C9C1199",
-      "hex")); // no 0x prefix hex
-    const dest_chain_mint_addr = bs58.encode(Buffer.from(
-      process.env.DEST_CHAIN_MINT_ADDR || "610178da211fef7d417bc0e6fed39f05609ad788",
-      "hex"
-    ));
-    const SOLANA_CHAIN_ID = 1;
-    await initTokenBridgeCall(rpc, program, signer, dest_chain_id, dest_chain_mint_addr, SOLANA_CHAIN_ID, mint.toString());
 
-    await initTokenBridgeCall(rpc, program, signer, SOLANA_CHAIN_ID, mint.toString(), dest_chain_id, dest_chain_mint_addr);
+    await setChainConfigCall(rpc, program, signer, dest_chain_id, { evm: {} }, 12, { groth16: { circuitId: ETH_DEPOSIT_CIRCUIT_ID } });
+    await setChainConfigCall(rpc, program, signer, SOLANA_CHAIN_ID, { solana: {} }, 32, { relayer: {} });
+    // the hardhat BridgeToken also has 2 decimals
+    const destDecimals = 2;
+    await initTokenBridgeCall(rpc, program, signer, dest_chain_id, dest_chain_mint_addr, destDecimals, SOLANA_CHAIN_ID, mint.toString(), decimals);
+
+    await initTokenBridgeCall(rpc, program, signer, SOLANA_CHAIN_ID, mint.toString(), decimals, dest_chain_id, dest_chain_mint_addr, destDecimals);
+    // liquidity for withdrawals into solana, held in the vault both directions share
+    await depositToTokenVault(rpc, signer, program, mint, dest_chain_id, dest_chain_mint_addr, SOLANA_CHAIN_ID, mint.toString());
 
     await CreateDepositRecordCompressedAccount(
       rpc,
@@ -133,17 +165,18 @@ describe("test-anchor", () => {
       dest_chain_addr,
     );
 
-    const withdrawKp = anchor.web3.Keypair.fromSecretKey(new Uint8Array(JSON.parse(fs.readFileSync(path.join(__dirname, "../../keys/signer.json"), "utf8"))));
-    const withdrawalRecordSeed = deriveAddressSeed(
-      [
-        new TextEncoder().encode("withdrawal"),
-        withdrawKp.publicKey.toBytes(),
-        Buffer.from(withdrawalNullifier),
-      ],
-      new web3.PublicKey(program.idl.address)
-    )
-    const withdrawalRecordAddress = deriveAddress(withdrawalRecordSeed, addressTree);
-    console.log("withdrawalRecordAddress", withdrawalRecordAddress);
+    scenario = {
+      rpc,
+      signer,
+      mint,
+      addressTree,
+      addressQueue,
+      outputMerkleTree,
+      destChainId: dest_chain_id,
+      destChainMintAddr: dest_chain_mint_addr,
+      depositRecordAddress,
+      nullifierTree: new IndexedMerkleTree(),
+    };
 
     // await CreateWithdrawalRecordCompressedAccount(
     //   rpc,
@@ -218,25 +251,401 @@ describe("test-anchor", () => {
     // );
     // console.log("deletedCounterAccount ", deletedCounterAccount);
   });
+
+  it("rejects a withdrawal nullifier replayed to a different recipient", async () => {
+    const { rpc, signer, mint, addressTree, addressQueue, outputMerkleTree, destChainId, destChainMintAddr, nullifierTree } = scenario;
+    const SOLANA_CHAIN_ID = 1;
+    const withdrawKp = anchor.web3.Keypair.fromSecretKey(new Uint8Array(JSON.parse(fs.readFileSync(path.join(__dirname, "../../keys/signer.json"), "utf8"))));
+    const withdrawal = await proveWithdrawal(nullifierTree, destChainId, destChainMintAddr, withdrawKp.publicKey, mint, BigInt(1000), 1);
+    const withdrawalRecordAddress = withdrawalRecordAddressFor(program, withdrawal.nullifier, addressTree);
+    // fetched while the address is still free, so the replay reaches the program
+    const freshAddressProof = await rpc.getValidityProofV0(
+      [],
+      [{ tree: addressTree, queue: addressQueue, address: bn(withdrawalRecordAddress.toBytes()) }],
+    );
+
+    // first payout, to the recipient the proof was generated for
+    await CreateWithdrawalRecordCompressedAccount(
+      rpc,
+      addressTree,
+      addressQueue,
+      program,
+      outputMerkleTree,
+      signer,
+      mint,
+      withdrawKp,
+      withdrawal,
+      destChainId,
+      destChainMintAddr,
+      SOLANA_CHAIN_ID,
+      mint.toString(),
+    );
+
+    // The proof is tied to the pre-insertion nullifier root and its recipient,
+    // so the replay fails on the stale root before it gets to the proof or the
+    // nullifier's withdrawal record.
+    const otherRecipient = new web3.Keypair();
+    let error;
+    try {
+      await withdrawCall(
+        rpc,
+        addressTree,
+        addressQueue,
+        program,
+        outputMerkleTree,
+        signer,
+        mint,
+        otherRecipient.publicKey,
+        withdrawal,
+        withdrawalVkVersion,
+        destChainId,
+        destChainMintAddr,
+        SOLANA_CHAIN_ID,
+        mint.toString(),
+        freshAddressProof,
+      );
+    } catch (err) {
+      error = err;
+    }
+    expect(error, "nullifier was paid out twice").to.not.be.undefined;
+    const logs = [String(error), ...(error.logs ?? [])].join("\n");
+    expect(logs).to.include("StaleNullifierRoot");
+  });
+
+  it("rejects a verifying key that accepts any proof", async () => {
+    const { rpc, signer } = scenario;
+    let error;
+    try {
+      await registerAcceptAnyProofKeyCall(rpc, program, signer, ACCEPT_ANY_PROOF_VK_VERSION);
+    } catch (err) {
+      error = err;
+    }
+    expect(error, "degenerate key was registered").to.not.be.undefined;
+    const logs = [String(error), ...(error.logs ?? [])].join("\n");
+    expect(logs).to.include("InvalidVerifyingKey");
+  });
+
+  it("gives consecutive withdrawals on a link consecutive ids", async () => {
+    const { rpc, signer, mint, addressTree, addressQueue, outputMerkleTree, destChainId, destChainMintAddr, nullifierTree } = scenario;
+    const SOLANA_CHAIN_ID = 1;
+    const withdrawKp = anchor.web3.Keypair.fromSecretKey(new Uint8Array(JSON.parse(fs.readFileSync(path.join(__dirname, "../../keys/signer.json"), "utf8"))));
+    const coder = new anchor.BorshCoder(idl as anchor.Idl);
+
+    const ids: bigint[] = [];
+    for (const depositId of [2, 3]) {
+      const withdrawal = await proveWithdrawal(nullifierTree, destChainId, destChainMintAddr, withdrawKp.publicKey, mint, BigInt(1000), depositId);
+      const sig = await CreateWithdrawalRecordCompressedAccount(
+        rpc,
+        addressTree,
+        addressQueue,
+        program,
+        outputMerkleTree,
+        signer,
+        mint,
+        withdrawKp,
+        withdrawal,
+        destChainId,
+        destChainMintAddr,
+        SOLANA_CHAIN_ID,
+        mint.toString(),
+      );
+
+      const event = (await cpiEvents(rpc, program, sig)).find((e) => e.name === "withdrawEvent");
+      expect(event, "no WithdrawEvent emitted").to.not.be.undefined;
+      const recordAccount = await rpc.getCompressedAccount(
+        bn(withdrawalRecordAddressFor(program, withdrawal.nullifier, addressTree).toBytes())
+      );
+      const record = coder.types.decode("WithdrawalRecordCompressedAccount", recordAccount.data.data);
+      expect(record.withdrawalId.toString(), "record and event ids differ").to.equal(event.data.withdrawalId.toString());
+      ids.push(BigInt(event.data.withdrawalId.toString()));
+    }
+    expect(ids[1]).to.equal(ids[0] + BigInt(1));
+  });
+
+  it("refuses to refund a deposit through another link to the same chain", async () => {
+    const { rpc, signer, mint, outputMerkleTree, destChainId, depositRecordAddress } = scenario;
+    const SOLANA_CHAIN_ID = 1;
+    // same solana mint and destination chain, another destination token
+    const otherDestChainMintAddr = bs58.encode(web3.Keypair.generate().publicKey.toBytes().slice(0, 20));
+    await initTokenBridgeCall(rpc, program, signer, SOLANA_CHAIN_ID, mint.toString(), 2, destChainId, otherDestChainMintAddr, 2);
+    const otherLinkHash = deriveLinkHash(SOLANA_CHAIN_ID, mint.toString(), destChainId, otherDestChainMintAddr);
+    await setTokenBridgeRefundTimeoutCall(rpc, program, signer, otherLinkHash, 1);
+    await sleep(2000);
+
+    let error;
+    try {
+      await refundDepositCall(rpc, program, outputMerkleTree, signer, mint, otherLinkHash, depositRecordAddress);
+    } catch (err) {
+      error = err;
+    }
+    expect(error, "deposit was refunded through another link").to.not.be.undefined;
+    const logs = [String(error), ...(error.logs ?? [])].join("\n");
+    expect(logs).to.include("InvalidArgs");
+  });
+
+  it("reports the vault shared by both directions as solvent", async () => {
+    const { rpc, signer, mint, destChainId, destChainMintAddr } = scenario;
+    const SOLANA_CHAIN_ID = 1;
+    const linkHash = deriveLinkHash(SOLANA_CHAIN_ID, mint.toString(), destChainId, destChainMintAddr);
+    const reverseLinkHash = deriveLinkHash(destChainId, destChainMintAddr, SOLANA_CHAIN_ID, mint.toString());
+
+    const tx = await program.methods
+      .checkSolvency(linkHash, true)
+      .accounts({
+        signer: signer.publicKey,
+        mint,
+        reverseTokenBridge: PublicKey.findProgramAddressSync(
+          [Buffer.from("tb"), Buffer.from(reverseLinkHash)],
+          program.programId
+        )[0],
+        tokenVault: tokenVaultPda(program, linkHash),
+      })
+      .signers([signer])
+      .transaction();
+    tx.recentBlockhash = (await rpc.getRecentBlockhash()).blockhash;
+    const sig = await rpc.sendTransaction(tx, [signer]);
+    await rpc.confirmTransaction(sig);
+
+    const events = await cpiEvents(rpc, program, sig);
+    const event = events.find((e) => e.name === "solvencyCheckEvent");
+    expect(event, "no SolvencyCheckEvent emitted").to.not.be.undefined;
+    expect(event.data.reverseTokenBridge, "reverse link not included").to.not.be.null;
+    expect(event.data.solvent).to.be.true;
+    expect(event.data.paused).to.be.false;
+  });
 });
 
+// events are emitted with emit_cpi!, as self-CPIs carrying the event
+// instruction tag followed by the encoded event
+const EVENT_IX_TAG = Buffer.from("e445a52e51cb9a1d", "hex");
+
+async function cpiEvents(
+  rpc: Rpc,
+  program: anchor.Program<CrossChainTokenBridge>,
+  signature: string,
+) {
+  const tx = await rpc.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+  const accountKeys = tx.transaction.message.getAccountKeys({ accountKeysFromLookups: tx.meta.loadedAddresses });
+  const events = [];
+  for (const inner of tx.meta.innerInstructions ?? []) {
+    for (const ix of inner.instructions) {
+      if (!accountKeys.get(ix.programIdIndex)?.equals(program.programId)) continue;
+      const data = Buffer.from(bs58.decode(ix.data));
+      if (!data.subarray(0, 8).equals(EVENT_IX_TAG)) continue;
+      const event = program.coder.events.decode(data.subarray(8).toString("base64"));
+      if (event) events.push(event);
+    }
+  }
+  return events;
+}
+
+// only the program's upgrade authority may call `init`; it then hands admin to `signer`
 async function initInstructionCall(
   rpc: Rpc,
   program: anchor.Program<CrossChainTokenBridge>,
   signer: anchor.web3.Signer,
 ) {
-  let tx = await program.methods.init()
+  const upgradeAuthority = anchor.web3.Keypair.fromSecretKey(new Uint8Array(JSON.parse(fs.readFileSync(path.join(__dirname, "../../keys/signer.json"), "utf8"))));
+  const [programData] = web3.PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111"),
+  );
+  let tx = await program.methods.init(initialNullifierRoot)
   .accounts({
-    signer: signer.publicKey,
+    signer: upgradeAuthority.publicKey,
+    programData,
+  })
+  .signers([upgradeAuthority])
+  .transaction();
+  tx.add(await program.methods.proposeAdmin(signer.publicKey)
+  .accounts({
+    admin: upgradeAuthority.publicKey,
+  })
+  .instruction());
+  tx.add(await program.methods.acceptAdmin()
+  .accounts({
+    newAdmin: signer.publicKey,
+  })
+  .instruction());
+  tx.feePayer = signer.publicKey;
+  tx.recentBlockhash = (await rpc.getRecentBlockhash()).blockhash;
+
+  const sig = await rpc.sendTransaction(tx, [signer, upgradeAuthority]);
+  await rpc.confirmTransaction(sig);
+  console.log("Created bridge state account");
+}
+
+// BN254 base field, proof_a is passed negated
+const BN254_P = BigInt("21888242871839275222246405745257275088696311157297823662689037894645226208583");
+// BN254 scalar field, circuit inputs are reduced into it
+const BN254_R = BigInt("21888242871839275222246405745257275088548364400416034343698204186575808495617");
+
+const ETH_DEPOSIT_WASM = path.join(__dirname, "../../circom/ethDepositProof_js/ethDepositProof.wasm");
+const ETH_DEPOSIT_ZKEY = path.join(__dirname, "../../circom/ethDepositProof_js/1_0000.zkey");
+const ETH_DEPOSIT_VK = path.join(__dirname, "../../circom/ethDepositProof_js/verification_key.json");
+
+type ProvenWithdrawal = {
+  nullifier: number[];
+  amount: bigint;
+  proofA: number[];
+  proofB: number[];
+  proofC: number[];
+  oldRoot: number[];
+  newRoot: number[];
+};
+
+function pubkeyToField(pubkey: PublicKey): string {
+  return (BigInt("0x" + pubkey.toBuffer().toString("hex")) % BN254_R).toString();
+}
+
+// Proves an eth deposit of `amount` to `recipient` the way the relayer does and
+// inserts its nullifier into `nullifierTree`, which must mirror the program's root.
+async function proveWithdrawal(
+  nullifierTree: IndexedMerkleTree,
+  sourceChainId: number,
+  sourceChainMintAddr: string,
+  recipient: PublicKey,
+  mint: PublicKey,
+  amount: bigint,
+  depositId: number,
+): Promise<ProvenWithdrawal> {
+  const depositEvent = {
+    depositor: BigInt("0x8626f6940E2eb28930eFb4CeF49B2d1F2C9C1199").toString(),
+    sourceChainId: sourceChainId.toString(),
+    destChainId: "1",
+    destChainAddr: pubkeyToField(recipient),
+    destChainMintAddr: pubkeyToField(mint),
+    // the token bridge holds the EVM token as the base58 of its 20 bytes
+    tokenMint: BigInt("0x" + Buffer.from(bs58.decode(sourceChainMintAddr)).toString("hex")).toString(),
+    amount: amount.toString(),
+    timestamp: Math.floor(Date.now() / 1000).toString(),
+    depositId: depositId.toString(),
+  };
+  const nullifier = poseidon9(Object.values(depositEvent).map((v) => BigInt(v)));
+  const nonMembership = nullifierTree.createNonMembershipProof(nullifier);
+  const { proof, publicSignals } = await snarkjs.groth16.fullProve(
+    {
+      ...depositEvent,
+      nullifier: nullifier.toString(),
+      pre_val: BigInt(nonMembership.preLeaf.val).toString(),
+      pre_next: BigInt(nonMembership.preLeaf.nextVal).toString(),
+      path: nonMembership.path.map((x) => BigInt(x).toString()),
+      dirs: nonMembership.directions.map(String),
+      old_root: BigInt(nonMembership.root).toString(),
+    },
+    ETH_DEPOSIT_WASM,
+    ETH_DEPOSIT_ZKEY,
+  );
+  await nullifierTree.insert(nullifier);
+
+  // public signals: nullifier, new_root, sourceChainId, recipient, mint, tokenMint, amount, old_root
+  return {
+    nullifier: fieldToBytes(publicSignals[0]),
+    amount,
+    proofA: g1ToBytes([proof.pi_a[0], ((BN254_P - BigInt(proof.pi_a[1])) % BN254_P).toString()]),
+    proofB: g2ToBytes(proof.pi_b),
+    proofC: g1ToBytes(proof.pi_c),
+    oldRoot: fieldToBytes(publicSignals[7]),
+    newRoot: fieldToBytes(publicSignals[1]),
+  };
+}
+
+// withdrawal records are addressed by their nullifier alone
+function withdrawalRecordAddressFor(
+  program: anchor.Program<CrossChainTokenBridge>,
+  nullifier: number[],
+  addressTree: PublicKey,
+): PublicKey {
+  const seed = deriveAddressSeed(
+    [new TextEncoder().encode("withdrawal"), Buffer.from(nullifier)],
+    program.programId,
+  );
+  return deriveAddress(seed, addressTree);
+}
+
+async function registerVerifyingKeyCall(
+  rpc: Rpc,
+  program: anchor.Program<CrossChainTokenBridge>,
+  signer: anchor.web3.Keypair,
+  version: number,
+  vk = JSON.parse(fs.readFileSync(ETH_DEPOSIT_VK, "utf8")),
+) {
+  const ic = vk.IC.map(g1ToBytes);
+
+  // the full IC doesn't fit in one transaction for larger circuits, upload it in two parts
+  const registerTx = await program.methods.registerVerifyingKey(
+    ETH_DEPOSIT_CIRCUIT_ID,
+    version,
+    vk.nPublic,
+    g1ToBytes(vk.vk_alpha_1),
+    g2ToBytes(vk.vk_beta_2),
+    g2ToBytes(vk.vk_gamma_2),
+    g2ToBytes(vk.vk_delta_2),
+    ic.slice(0, 2),
+  )
+  .accounts({
+    admin: signer.publicKey,
+  })
+  .signers([signer])
+  .transaction();
+  const appendTx = await program.methods.appendVerifyingKeyIc(ETH_DEPOSIT_CIRCUIT_ID, version, ic.slice(2))
+  .accounts({
+    admin: signer.publicKey,
+  })
+  .signers([signer])
+  .transaction();
+  const activateTx = await program.methods.activateVerifyingKey(ETH_DEPOSIT_CIRCUIT_ID, version)
+  .accounts({
+    admin: signer.publicKey,
+  })
+  .signers([signer])
+  .transaction();
+
+  for (const tx of [registerTx, appendTx, activateTx]) {
+    tx.recentBlockhash = (await rpc.getRecentBlockhash()).blockhash;
+    tx.sign(signer);
+    const sig = await rpc.sendTransaction(tx, [signer]);
+    await rpc.confirmTransaction(sig);
+  }
+  console.log("Registered eth deposit verifying key version", version);
+}
+
+// With alpha and every IC point at infinity, a proof whose A and C are at
+// infinity too pairs to one for any public signals.
+async function registerAcceptAnyProofKeyCall(
+  rpc: Rpc,
+  program: anchor.Program<CrossChainTokenBridge>,
+  signer: anchor.web3.Keypair,
+  version: number,
+) {
+  const vk = JSON.parse(fs.readFileSync(ETH_DEPOSIT_VK, "utf8"));
+  await registerVerifyingKeyCall(rpc, program, signer, version, {
+    ...vk,
+    vk_alpha_1: ["0", "0"],
+    IC: vk.IC.map(() => ["0", "0"]),
+  });
+}
+
+async function setChainConfigCall(
+  rpc: Rpc,
+  program: anchor.Program<CrossChainTokenBridge>,
+  signer: anchor.web3.Signer,
+  chainId: number,
+  addressFormat: any,
+  confirmations: number,
+  verifier: any,
+) {
+  const tx = await program.methods.setChainConfig(chainId, true, addressFormat, confirmations, verifier)
+  .accounts({
+    admin: signer.publicKey,
   })
   .signers([signer])
   .transaction();
   tx.recentBlockhash = (await rpc.getRecentBlockhash()).blockhash;
   tx.sign(signer);
-
   const sig = await rpc.sendTransaction(tx, [signer]);
   await rpc.confirmTransaction(sig);
-  console.log("Created bridge state account");
+  console.log("chain config set", chainId, sig);
 }
 
 async function initTokenBridgeCall(
@@ -245,22 +654,30 @@ async function initTokenBridgeCall(
   signer: anchor.web3.Signer,
   source_chain: number,
   source_chain_mint_addr: string,
+  source_decimals: number,
   dest_chain_id: number,
   dest_chain_mint_addr: string,
+  dest_decimals: number,
 ) {
   {
-    const linkHash = require('crypto').createHash('sha256').update(`${source_chain}_${source_chain_mint_addr}_${dest_chain_id}_${dest_chain_mint_addr}`).digest('hex').slice(0, 16);
+    const linkHash = deriveLinkHash(source_chain, source_chain_mint_addr, dest_chain_id, dest_chain_mint_addr);
     console.log("initTokenBridgeCall called", linkHash)
     let tx = await program.methods.initTokenBridge(
       source_chain,
       source_chain_mint_addr,
       dest_chain_id,
       dest_chain_mint_addr,
-      linkHash,
+      { lockRelease: {} },
+      source_decimals,
+      dest_decimals,
     )
     .accounts(
       {
         signer:signer.publicKey,
+        // seeded by the link hash the program derives from the four args above
+        tokenBridge: PublicKey.findProgramAddressSync([Buffer.from("tb"), Buffer.from(linkHash)], program.programId)[0],
+        // checked for unsupported token-2022 extensions
+        mint: new PublicKey(source_chain === 1 ? source_chain_mint_addr : dest_chain_mint_addr),
       }
     )
     .signers([signer])
@@ -283,18 +700,120 @@ async function initTokenBridgeCall(
   }
 }
 
+function vaultLinkPda(
+  program: anchor.Program<CrossChainTokenBridge>,
+  seed: string,
+  vaultLinkHash: string,
+): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from(seed), Buffer.from(vaultLinkHash)],
+    program.programId
+  )[0];
+}
+
+function tokenVaultPda(
+  program: anchor.Program<CrossChainTokenBridge>,
+  vaultLinkHash: string,
+): PublicKey {
+  return vaultLinkPda(program, "vault", vaultLinkHash);
+}
+
+function liquidityPoolPda(
+  program: anchor.Program<CrossChainTokenBridge>,
+  vaultLinkHash: string,
+): PublicKey {
+  return vaultLinkPda(program, "pool", vaultLinkHash);
+}
+
+// withdraw picks the circuit from the source chain's config, so the key is passed explicitly
+function verifyingKeyPda(
+  program: anchor.Program<CrossChainTokenBridge>,
+  circuitId: number,
+  version: number,
+): PublicKey {
+  const circuitIdBytes = Buffer.alloc(4);
+  circuitIdBytes.writeUInt32LE(circuitId);
+  const versionBytes = Buffer.alloc(4);
+  versionBytes.writeUInt32LE(version);
+  return web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("vk"), circuitIdBytes, versionBytes],
+    program.programId
+  )[0];
+}
+
+function withdrawalProofPda(
+  program: anchor.Program<CrossChainTokenBridge>,
+  creator: PublicKey,
+  nullifier: number[],
+): PublicKey {
+  return web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("withdrawal_proof"), creator.toBuffer(), Buffer.from(nullifier)],
+    program.programId
+  )[0];
+}
+
+// withdraw_with_proof only fits in a transaction with its accounts behind a lookup table
+async function sendWithLookupTable(
+  rpc: Rpc,
+  signer: anchor.web3.Keypair,
+  instructions: web3.TransactionInstruction[],
+) {
+  const [createIx, lookupTable] = web3.AddressLookupTableProgram.createLookupTable({
+    authority: signer.publicKey,
+    payer: signer.publicKey,
+    recentSlot: await rpc.getSlot("finalized"),
+  });
+  const addresses = [...new Set(
+    instructions.flatMap(ix => ix.keys.filter(k => !k.isSigner).map(k => k.pubkey.toBase58()))
+  )].map(k => new PublicKey(k));
+  const setup = [createIx];
+  for (let i = 0; i < addresses.length; i += 20) {
+    setup.push(web3.AddressLookupTableProgram.extendLookupTable({
+      lookupTable,
+      authority: signer.publicKey,
+      payer: signer.publicKey,
+      addresses: addresses.slice(i, i + 20),
+    }));
+  }
+  for (const ix of setup) {
+    const setupTx = new web3.Transaction().add(ix);
+    setupTx.recentBlockhash = (await rpc.getRecentBlockhash()).blockhash;
+    await rpc.confirmTransaction(await rpc.sendTransaction(setupTx, [signer]), "finalized");
+  }
+  const lookupTableAccount = (await rpc.getAddressLookupTable(lookupTable)).value;
+
+  const message = new web3.TransactionMessage({
+    payerKey: signer.publicKey,
+    recentBlockhash: (await rpc.getLatestBlockhash()).blockhash,
+    instructions,
+  }).compileToV0Message([lookupTableAccount]);
+  const tx = new web3.VersionedTransaction(message);
+  tx.sign([signer]);
+  const sig = await rpc.sendTransaction(tx);
+  await rpc.confirmTransaction(sig, "finalized");
+  return sig;
+}
+
 async function depositToTokenVault(
   rpc: Rpc,
   signer: anchor.web3.Keypair,
   program: anchor.Program<CrossChainTokenBridge>,
   mint: PublicKey,
+  source_chain: number,
+  source_chain_mint_addr: string,
+  dest_chain_id: number,
+  dest_chain_mint_addr: string,
 ) {
   {
+    const linkHash = deriveLinkHash(source_chain, source_chain_mint_addr, dest_chain_id, dest_chain_mint_addr);
     const tx = await program.methods
-    .depositToVault(bn(50 * 10 ** 2))
+    .depositToVault(linkHash, bn(50 * 10 ** 2))
     .accounts({
       signer: signer.publicKey,
       mint: mint,
+      tokenVault: tokenVaultPda(program, deriveVaultLinkHash(source_chain, source_chain_mint_addr, dest_chain_id, dest_chain_mint_addr)),
+      liquidityPool: liquidityPoolPda(program, deriveVaultLinkHash(source_chain, source_chain_mint_addr, dest_chain_id, dest_chain_mint_addr)),
+      lpMint: vaultLinkPda(program, "lp_mint", deriveVaultLinkHash(source_chain, source_chain_mint_addr, dest_chain_id, dest_chain_mint_addr)),
       tokenProgram: TOKEN_PROGRAM_ID
     })
     .signers([signer])
@@ -320,7 +839,7 @@ async function CreateDepositRecordCompressedAccount(
   source_chain_mint_addr: string,
   dest_chain_id: number,
   dest_chain_mint_addr: string,
-  dest_chain_addr: string,
+  dest_chain_addr: { evm: { address: number[]; checksummed: string | null } },
 ) {
   {
     let  proofRpcResult;
@@ -358,13 +877,15 @@ async function CreateDepositRecordCompressedAccount(
       units: 1_000_000,
     });
 
-    const linkHash = require('crypto').createHash('sha256').update(`${source_chain}_${source_chain_mint_addr}_${dest_chain_id}_${dest_chain_mint_addr}`).digest('hex').slice(0, 16);
+    const linkHash = deriveLinkHash(source_chain, source_chain_mint_addr, dest_chain_id, dest_chain_mint_addr);
     
     let tx = await program.methods
       .deposit(proof, packedAddressMerkleContext, outputMerkleTreeIndex, bn(100 * 10 ** 2), linkHash, dest_chain_addr)
       .accounts({
         signer: signer.publicKey,
         mint: mint,
+        tokenVault: tokenVaultPda(program, deriveVaultLinkHash(source_chain, source_chain_mint_addr, dest_chain_id, dest_chain_mint_addr)),
+        liquidityPool: liquidityPoolPda(program, deriveVaultLinkHash(source_chain, source_chain_mint_addr, dest_chain_id, dest_chain_mint_addr)),
         tokenProgram: TOKEN_PROGRAM_ID
       })
       .preInstructions([computeBudgeIx])
@@ -407,44 +928,33 @@ async function CreateDepositRecordCompressedAccount(
   }
 }
 
+// withdraws through the proof account, returns the withdraw transaction's signature
 async function CreateWithdrawalRecordCompressedAccount(
   rpc:Rpc,
   addressTree: anchor.web3.PublicKey,
   addressQueue: anchor.web3.PublicKey,
-  address: anchor.web3.PublicKey,
   program: anchor.Program<CrossChainTokenBridge>,
   outputMerkleTree: anchor.web3.PublicKey,
   signer: anchor.web3.Keypair,
   mint: PublicKey,
+  withdrawKp: anchor.web3.Keypair,
+  withdrawal: ProvenWithdrawal,
   source_chain: number,
   source_chain_mint_addr: string,
   dest_chain_id: number,
   dest_chain_mint_addr: string,
-) {
+): Promise<string> {
   {    
+    const address = withdrawalRecordAddressFor(program, withdrawal.nullifier, addressTree);
     // create withdrawalProof account and write the data into that account
     const withdrawalProofTx = await program.methods.initWithdrawalProofAccount(
       bn(2),
-      [14,58,244,221,122,68,66,81,213,157,63,61,7,190,118,65,192,146,144,180,155,55,213,242,31,230,7,79,51,113,237,169,44,205,233,37,188,227,130,185,222,44,198,182,102,234,116,74,16,151,178,93,26,55,87,92,176,81,238,23,165,142,209,226],
-      [
-        29, 228, 78, 154, 16, 24, 136, 0, 188, 126, 229, 20, 31, 194, 17, 160,
-        253, 155, 78, 80, 91, 86, 24, 143, 104, 190, 237, 89, 159, 96, 108, 20,
-        17, 105, 151, 153, 180, 40, 3, 122, 6, 6, 96, 121, 76, 21, 164, 49, 171,
-        151, 154, 49, 112, 89, 132, 205, 150, 111, 119, 28, 8, 9, 4, 160, 0, 15,
-        4, 58, 220, 188, 189, 143, 75, 171, 181, 8, 244, 237, 255, 228, 31, 255,
-        248, 187, 170, 208, 237, 155, 11, 54, 239, 104, 123, 184, 177, 15, 23,
-        252, 94, 41, 34, 188, 219, 220, 139, 127, 198, 61, 184, 94, 165, 146, 124,
-        223, 46, 70, 47, 214, 223, 90, 199, 211, 45, 249, 195, 219, 124, 159
-      ],
-      [
-        5, 41, 227, 187, 40, 74, 192, 30, 223, 107, 115, 187, 177, 209, 57, 201,
-        113, 19, 103, 129, 144, 182, 119, 147, 215, 161, 216, 125, 67, 65, 226,
-        94, 29, 97, 209, 152, 187, 206, 57, 209, 247, 198, 56, 91, 63, 153, 126,
-        149, 235, 186, 238, 198, 95, 85, 199, 231, 59, 143, 94, 71, 196, 174, 124,
-        211
-      ],
-      withdrawalNullifier,
-      [10,176,51,106,94,59,78,39,15,155,59,130,38,103,174,242,118,76,148,79,204,137,57,87,102,125,171,241,60,92,147,48]
+      withdrawal.proofA,
+      withdrawal.proofB,
+      withdrawal.proofC,
+      withdrawal.nullifier,
+      withdrawal.oldRoot,
+      withdrawal.newRoot,
     )
     .accounts({
       signer: signer.publicKey,
@@ -459,8 +969,6 @@ async function CreateWithdrawalRecordCompressedAccount(
     await rpc.confirmTransaction(sig1, "finalized");
     console.log("created withdrawal proof", sig1);
 
-    const withdrawKp = anchor.web3.Keypair.fromSecretKey(new Uint8Array(JSON.parse(fs.readFileSync(path.join(__dirname, "../../keys/signer.json"), "utf8"))));
-
     // get the balance of mint tokens for withdrawKp
     const withdrawKpAta = await getOrCreateAssociatedTokenAccount(
       new Connection("http://localhost:8899", "confirmed"),
@@ -500,7 +1008,7 @@ async function CreateWithdrawalRecordCompressedAccount(
     }
     console.log(1)
 
-    const linkHash = require('crypto').createHash('sha256').update(`${source_chain}_${source_chain_mint_addr}_${dest_chain_id}_${dest_chain_mint_addr}`).digest('hex').slice(0, 16);
+    const linkHash = deriveLinkHash(source_chain, source_chain_mint_addr, dest_chain_id, dest_chain_mint_addr);
     console.log(3)
 
     const computeBudgetIx = web3.ComputeBudgetProgram.setComputeUnitLimit({
@@ -511,15 +1019,22 @@ async function CreateWithdrawalRecordCompressedAccount(
       proof,
       packedAddressMerkleContext,
       outputMerkleTreeIndex,
-      bn(50 * 10*2),
+      bn(withdrawal.amount.toString()),
       linkHash,
-      withdrawalNullifier,
+      withdrawal.nullifier,
+      withdrawalVkVersion,
     )
     .accounts({
-      relayer: signer.publicKey,
-      recipient: withdrawKp.publicKey,
-      mint: mint,
-      tokenProgram: TOKEN_PROGRAM_ID
+      withdraw: {
+        relayer: signer.publicKey,
+        verifyingKey: verifyingKeyPda(program, ETH_DEPOSIT_CIRCUIT_ID, withdrawalVkVersion),
+        recipient: withdrawKp.publicKey,
+        mint: mint,
+        tokenVault: tokenVaultPda(program, deriveVaultLinkHash(source_chain, source_chain_mint_addr, dest_chain_id, dest_chain_mint_addr)),
+        liquidityPool: liquidityPoolPda(program, deriveVaultLinkHash(source_chain, source_chain_mint_addr, dest_chain_id, dest_chain_mint_addr)),
+        tokenProgram: TOKEN_PROGRAM_ID
+      },
+      withdrawalProof: withdrawalProofPda(program, signer.publicKey, withdrawal.nullifier),
     })
     .preInstructions([computeBudgetIx])
     .remainingAccounts(remainingAccounts.toAccountMetas().remainingAccounts)
@@ -573,9 +1088,165 @@ async function CreateWithdrawalRecordCompressedAccount(
   );
   console.log("withdrawKp token balance after withdrawal:", withdrawKpBalance2.amount.toString(), mint.toString());
 
+  return sig;
   }
 }
 
+// addressProof defaults to a fresh non-inclusion proof of the withdrawal record's address
+async function withdrawCall(
+  rpc: Rpc,
+  addressTree: anchor.web3.PublicKey,
+  addressQueue: anchor.web3.PublicKey,
+  program: anchor.Program<CrossChainTokenBridge>,
+  outputMerkleTree: anchor.web3.PublicKey,
+  signer: anchor.web3.Keypair,
+  mint: PublicKey,
+  recipient: PublicKey,
+  withdrawal: ProvenWithdrawal,
+  vkVersion: number,
+  source_chain: number,
+  source_chain_mint_addr: string,
+  dest_chain_id: number,
+  dest_chain_mint_addr: string,
+  addressProof?: Awaited<ReturnType<Rpc["getValidityProofV0"]>>,
+) {
+  const address = withdrawalRecordAddressFor(program, withdrawal.nullifier, addressTree);
+  const proofRpcResult = addressProof ?? await rpc.getValidityProofV0(
+    [],
+    [
+      {
+        tree: addressTree,
+        queue: addressQueue,
+        address: bn(address.toBytes())
+      }
+    ]
+  );
+
+  const systemAccountConfig = SystemAccountMetaConfig.new(program.programId);
+  let remainingAccounts = PackedAccounts.newWithSystemAccounts(systemAccountConfig);
+  const addressMerkleTreePubkeyIndex = remainingAccounts.insertOrGet(addressTree);
+  const addressQueuePubkeyIndex = remainingAccounts.insertOrGet(addressQueue);
+  const packedAddressMerkleContext = {
+    rootIndex: proofRpcResult.rootIndices[0],
+    addressMerkleTreePubkeyIndex,
+    addressQueuePubkeyIndex
+  };
+  const outputMerkleTreeIndex = remainingAccounts.insertOrGet(outputMerkleTree);
+  let proof = {
+    0: proofRpcResult.compressedProof,
+  }
+
+  const linkHash = deriveLinkHash(source_chain, source_chain_mint_addr, dest_chain_id, dest_chain_mint_addr);
+  const computeBudgetIx = web3.ComputeBudgetProgram.setComputeUnitLimit({
+    units: 1_000_000,
+  });
+  const ix = await program.methods
+  .withdrawWithProof(
+    proof,
+    packedAddressMerkleContext,
+    outputMerkleTreeIndex,
+    bn(withdrawal.amount.toString()),
+    linkHash,
+    withdrawal.nullifier,
+    vkVersion,
+    withdrawal.proofA,
+    withdrawal.proofB,
+    withdrawal.proofC,
+    withdrawal.oldRoot,
+    withdrawal.newRoot,
+  )
+  .accounts({
+    withdraw: {
+      relayer: signer.publicKey,
+      verifyingKey: verifyingKeyPda(program, ETH_DEPOSIT_CIRCUIT_ID, vkVersion),
+      recipient,
+      mint: mint,
+      tokenVault: tokenVaultPda(program, deriveVaultLinkHash(source_chain, source_chain_mint_addr, dest_chain_id, dest_chain_mint_addr)),
+      liquidityPool: liquidityPoolPda(program, deriveVaultLinkHash(source_chain, source_chain_mint_addr, dest_chain_id, dest_chain_mint_addr)),
+      tokenProgram: TOKEN_PROGRAM_ID
+    },
+  })
+  .remainingAccounts(remainingAccounts.toAccountMetas().remainingAccounts)
+  .instruction();
+
+  return sendWithLookupTable(rpc, signer, [computeBudgetIx, ix]);
+}
+
+async function setTokenBridgeRefundTimeoutCall(
+  rpc: Rpc,
+  program: anchor.Program<CrossChainTokenBridge>,
+  signer: anchor.web3.Keypair,
+  linkHash: string,
+  refundTimeout: number,
+) {
+  const tx = await program.methods.setTokenBridgeRefundTimeout(linkHash, bn(refundTimeout))
+  .accounts({
+    admin: signer.publicKey,
+  })
+  .signers([signer])
+  .transaction();
+  tx.recentBlockhash = (await rpc.getRecentBlockhash()).blockhash;
+  tx.sign(signer);
+  await rpc.confirmTransaction(await rpc.sendTransaction(tx, [signer]));
+}
+
+async function refundDepositCall(
+  rpc: Rpc,
+  program: anchor.Program<CrossChainTokenBridge>,
+  outputMerkleTree: anchor.web3.PublicKey,
+  signer: anchor.web3.Keypair,
+  mint: PublicKey,
+  linkHash: string,
+  depositRecordAddress: PublicKey,
+) {
+  const depositRecordAccount = await rpc.getCompressedAccount(bn(depositRecordAddress.toBytes()));
+  // camelCase, as the instruction's args are encoded
+  const depositRecord = program.coder.types.decode("DepositRecordCompressedAccount", depositRecordAccount.data.data);
+  const proofRpcResult = await rpc.getValidityProofV0(
+    [
+      {
+        hash: depositRecordAccount.hash,
+        tree: depositRecordAccount.treeInfo.tree,
+        queue: depositRecordAccount.treeInfo.queue,
+      },
+    ],
+    [],
+  );
+  const systemAccountConfig = SystemAccountMetaConfig.new(program.programId);
+  let remainingAccounts = PackedAccounts.newWithSystemAccounts(systemAccountConfig);
+  const merkleTreePubkeyIndex = remainingAccounts.insertOrGet(depositRecordAccount.treeInfo.tree);
+  const queuePubkeyIndex = remainingAccounts.insertOrGet(depositRecordAccount.treeInfo.queue);
+  const outputMerkleTreeIndex = remainingAccounts.insertOrGet(outputMerkleTree);
+  const accountMeta = {
+    merkleContext: {
+      merkleTreePubkeyIndex,
+      queuePubkeyIndex,
+      leafIndex: depositRecordAccount.leafIndex,
+      proveByIndex: false,
+    },
+    rootIndex: proofRpcResult.rootIndices[0],
+    outputMerkleTreeIndex,
+    address: depositRecordAccount.address,
+  };
+
+  const tx = await program.methods
+  .refundDeposit({ 0: proofRpcResult.compressedProof }, linkHash, depositRecord, accountMeta)
+  .accounts({
+    signer: signer.publicKey,
+    mint,
+    // the handler rejects the link before it touches a vault
+    tokenVault: null,
+    liquidityPool: null,
+    tokenProgram: TOKEN_PROGRAM_ID,
+  })
+  .remainingAccounts(remainingAccounts.toAccountMetas().remainingAccounts)
+  .signers([signer])
+  .transaction();
+  tx.recentBlockhash = (await rpc.getRecentBlockhash()).blockhash;
+  tx.sign(signer);
+  await rpc.confirmTransaction(await rpc.sendTransaction(tx, [signer]));
+}
+
 async function CreateCounterCompressedAccount(
   rpc: Rpc,
   addressTree: anchor.web3.PublicKey,
@@ -177,7 +177,12 @@ async function createSolDeposit(
   });
 
   // solDepositParams
-  solDepositParams.destChainAddr = bs58.encode(Buffer.from(solDepositParams.destChainAddr.replace('0x', ""), "hex"));
+  const destChainAddr = {
+    evm: {
+      address: Array.from(Buffer.from(solDepositParams.destChainAddr.replace('0x', ""), "hex")),
+      checksummed: null,
+    },
+  };
   solDepositParams.destChainMintAddr = bs58.encode(Buffer.from(solDepositParams.destChainMintAddr.replace('0x', ""), "hex"));
 
   const link = `${CONSTANTS.SOLANA_CHAIN_ID}_${process.env.NEXT_PUBLIC_SOLANA_BRIDGE_TOKEN_MINT_ADDR}_${solDepositParams.destChainId}_${solDepositParams.destChainMintAddr}`
@@ -196,7 +201,7 @@ async function createSolDeposit(
     outputMerkleTreeIndex,
     bn(solDepositParams.amountLamports.toString()),
     linkHash,
-    solDepositParams.destChainAddr
+    destChainAddr
   )
   .accounts({
     signer: wallet.publicKey,